@@ -1,14 +1,20 @@
-use std::time::SystemTime;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 
-use crate::{config, audio, recorder, listener, stt, commands, COMMANDS_LIST};
+use crate::{config, audio, recorder, listener, stt, vad, normalizer, commands, COMMANDS_LIST, DB};
+use crate::commands::{AssistantCommand, Candidate, Config, FetchResult, Slots};
 use rand::seq::SliceRandom;
 
-pub fn start() -> Result<(), ()> {
+pub fn start(dry_run: bool) -> Result<(), ()> {
+    if dry_run {
+        info!("Dry run, commands won't be executed.");
+    }
+
     // start the loop
-    main_loop()
+    main_loop(dry_run)
 }
 
-fn main_loop() -> Result<(), ()> {
+fn main_loop(dry_run: bool) -> Result<(), ()> {
     let mut start: SystemTime;
     let sounds_directory = audio::get_sound_directory().unwrap();
     let frame_length: usize = 512; // default for every wake-word engine
@@ -29,51 +35,116 @@ fn main_loop() -> Result<(), ()> {
 
     // the loop
     'wake_word: loop {
+        // apply reloaded commands, if any
+        commands::watcher::apply_pending();
+
+        // reap finished (or timed out) command processes
+        commands::supervisor::reap();
+
         // read from microphone
         recorder::read_microphone(&mut frame_buffer);
 
+        // keep following the background noise, but don't gate the wake-word engine,
+        // it needs continuous audio (including the silence right after the wake-word)
+        vad::is_speech(&frame_buffer);
+
         // recognize wake-word
         match listener::data_callback(&frame_buffer) {
-            Some(keyword_index) => {
+            Some(wake_word) => {
                 // wake-word activated, process further commands
+                info!("Wake-word {:?} detected.", wake_word);
+                let behaviour = listener::behaviour(&wake_word);
+
+                // commands set stays the same until we return to wake-word listening
+                let commands_list = COMMANDS_LIST.get().unwrap().load_full();
+
+                // some wake-words run the command right away
+                if let Some(id) = behaviour.and_then(|b| b.action.as_ref()) {
+                    run_wake_word_action(id, &commands_list, &mut frame_buffer, &sounds_directory, dry_run);
+                    continue 'wake_word;
+                }
+
                 // capture current time
                 start = SystemTime::now();
 
                 // play some greet phrase
                 // @TODO. Make it via commands or upcoming events system.
-                audio::play_sound(&sounds_directory.join(format!("{}.wav", config::ASSISTANT_GREET_PHRASES.choose(&mut rand::thread_rng()).unwrap())));
+                let greet_sounds = match behaviour {
+                    Some(behaviour) if !behaviour.greet_sounds.is_empty() => behaviour.greet_sounds.clone(),
+                    _ => config::ASSISTANT_GREET_PHRASES.iter().map(|x| String::from(*x)).collect()
+                };
+                audio::play_sound(&sounds_directory.join(format!("{}.wav", greet_sounds.choose(&mut rand::thread_rng()).unwrap())));
+
+                // whether the user started talking, and when the speech was last heard
+                let mut speaking = false;
+                let mut last_speech = SystemTime::now();
+                let mut pre_speech = vad::PreSpeech::default();
 
                 // wait for voice commands
                 'voice_recognition: loop {
                     // read from microphone
                     recorder::read_microphone(&mut frame_buffer);
 
+                    let is_speech = vad::is_speech(&frame_buffer);
+                    if is_speech {
+                        last_speech = SystemTime::now();
+                    }
+
                     // stt part (without partials)
-                    if let Some(mut recognized_voice) = stt::recognize(&frame_buffer, false) {
+                    // silence before the speech is skipped (but its last moment, vad may be late),
+                    // the one after is needed to finish the phrase
+                    let recognized = if speaking {
+                        stt::recognize_alternatives(&frame_buffer)
+                    } else if is_speech {
+                        speaking = true;
+                        stt::recognize_alternatives(&pre_speech.take_with(&frame_buffer))
+                    } else {
+                        pre_speech.push(&frame_buffer);
+                        None
+                    };
+
+                    if let Some(alternatives) = recognized {
                         // something was recognized
-                        info!("Recognized voice: {}", recognized_voice);
+                        info!("Recognized voice alternatives: {:?}", alternatives);
 
                         // filter recognized voice
-                        // @TODO. Better recognized voice filtration.
-                        recognized_voice = recognized_voice.to_lowercase();
-                        for tbr in config::ASSISTANT_PHRASES_TBR {
-                            recognized_voice = recognized_voice.replace(tbr, "");
-                        }
-                        recognized_voice = recognized_voice.trim().into();
+                        let recognized_voice = filter_alternatives(&alternatives);
 
                         // infer command
-                        if let Some((cmd_path, cmd_config)) = commands::fetch_command(&recognized_voice, &COMMANDS_LIST.get().unwrap()) {
+                        let command = match commands::fetch_command(&recognized_voice, &commands_list, &wake_word) {
+                            FetchResult::Found(candidate) => Some(candidate),
+                            FetchResult::Ambiguous(options) => {
+                                // ask which one was meant
+                                clarify(options, &mut frame_buffer, &sounds_directory)
+                            },
+                            FetchResult::NotFound => None
+                        };
+
+                        // dangerous commands must be confirmed first
+                        let command = command.filter(|c| confirmed(c.config, &commands_list, &mut frame_buffer, &sounds_directory, dry_run));
+
+                        if let Some(Candidate { path: cmd_path, config: cmd_config, slots, .. }) = command {
                             // some debug info
-                            info!("Recognized voice (filtered): {}", recognized_voice);
-                            info!("Command found: {:?}", cmd_path);
+                            info!("Recognized voice (filtered): {:?}", recognized_voice);
+                            info!("Command found: {} ({:?})", cmd_config.id, cmd_path);
+
+                            if dry_run {
+                                info!("Dry run, would execute {:?} with slots {:?}", cmd_config.command, slots);
+                                break 'voice_recognition;
+                            }
+
                             info!("Executing!");
 
                             // execute the command
-                            match commands::execute_command(&cmd_path, &cmd_config) {
+                            match commands::execute_command(&cmd_path, &cmd_config, &slots) {
                                 Ok(chain) => {
                                     // success
                                     info!("Command executed successfully.");
 
+                                    // the command may take a while, wait for the next phrase from now on
+                                    speaking = false;
+                                    last_speech = SystemTime::now();
+
                                     if chain {
                                         // chain commands
                                         start = SystemTime::now();
@@ -103,6 +174,16 @@ fn main_loop() -> Result<(), ()> {
                         },
                         _ => ()
                     }
+
+                    // or until the user stops talking (or doesn't start at all)
+                    let silence_timeout = if speaking { config::VAD_END_OF_SPEECH_TIMEOUT } else { config::VAD_NO_SPEECH_TIMEOUT };
+                    match last_speech.elapsed() {
+                        Ok(elapsed) if elapsed > silence_timeout => {
+                            info!("Silence for {:?}, returning to wake-word listening.", elapsed);
+                            break 'voice_recognition;
+                        },
+                        _ => ()
+                    }
                 }
             },
             None => ()
@@ -112,11 +193,246 @@ fn main_loop() -> Result<(), ()> {
     Ok(())
 }
 
-fn keyword_callback(keyword_index: i32) {
+// feed the text phrase into the same pipeline as recognized voice, returns exit code
+// `confirmed` - whether dangerous commands may run (there is no voice to confirm them)
+pub fn say(phrase: &str, dry_run: bool, confirmed: bool) -> i32 {
+    let recognized_voice = filter_alternatives(&[stt::Alternative { text: phrase.into(), confidence: 1. }]);
+    println!("Phrase (filtered): {:?}", recognized_voice);
+
+    let commands_list = COMMANDS_LIST.get().unwrap().load_full();
+    let candidate = match commands::fetch_command(&recognized_voice, &commands_list, "") {
+        FetchResult::Found(candidate) => candidate,
+        FetchResult::Ambiguous(options) => {
+            // there is nobody to ask
+            println!("Phrase is ambiguous, candidates are:");
+            for option in options {
+                println!("  {} ({:?}, score {:.1})", option.config.id, option.phrase, option.score);
+            }
+
+            return 1
+        },
+        FetchResult::NotFound => {
+            println!("No command found.");
+            return 1
+        }
+    };
+
+    println!("Command: {} ({})", commands::describe(candidate.config), candidate.path.display());
+    println!("Matched phrase: {:?}, score {:.1}", candidate.phrase, candidate.score);
+    println!("Slots: {:?}", candidate.slots);
+    println!("Action: {:?}", candidate.config.command);
+    let needs_confirmation = commands::needs_confirmation(candidate.config, &commands_list);
+    println!("Needs confirmation: {}", needs_confirmation);
+
+    if dry_run {
+        println!("Dry run, not executing.");
+        return 0
+    }
+
+    if needs_confirmation && !confirmed {
+        println!("Command requires confirmation, pass --yes to run it.");
+        return 1
+    }
+
+    match commands::execute_command(candidate.path, candidate.config, &candidate.slots) {
+        Ok(_) => {
+            println!("Command executed successfully.");
+            0
+        },
+        Err(msg) => {
+            println!("Error executing command: {}", msg);
+            1
+        }
+    }
+}
+
+// wait for the user to pick one of the ambiguous commands ("первый", "второй", ...)
+fn clarify<'a>(options: Vec<Candidate<'a>>, frame_buffer: &mut [i16], sounds_directory: &Path) -> Option<Candidate<'a>> {
+    play_phrase(sounds_directory, config::ASSISTANT_CLARIFY_PHRASE);
+
+    let reply = listen_for_reply(frame_buffer, config::CMD_CLARIFY_WAIT_DELAY)?;
+    info!("Clarification reply: {:?}", reply);
+
+    match commands::clarify_choice(&reply, options.len()) {
+        Some(choice) => {
+            info!("Option #{} chosen.", choice + 1);
+            options.into_iter().nth(choice)
+        },
+        None => {
+            info!("No option chosen, skipping.");
+            None
+        }
+    }
+}
+
+// whether the command may run, asks first if it needs confirmation
+fn confirmed(scmd: &Config, commands_list: &[AssistantCommand], frame_buffer: &mut [i16], sounds_directory: &Path, dry_run: bool) -> bool {
+    if !commands::needs_confirmation(scmd, commands_list) {
+        return true
+    }
 
+    if dry_run {
+        info!("Dry run, would ask to confirm {}.", scmd.id);
+        return true
+    }
+
+    confirm(frame_buffer, sounds_directory)
+}
+
+// ask whether the command should really be executed, anything but a clear "yes" cancels it
+fn confirm(frame_buffer: &mut [i16], sounds_directory: &Path) -> bool {
+    play_phrase(sounds_directory, config::ASSISTANT_CONFIRM_PHRASE);
+
+    let reply = match listen_for_reply(frame_buffer, config::CMD_CONFIRM_WAIT_DELAY) {
+        Some(reply) => reply,
+        None => {
+            info!("No confirmation received, cancelling the command.");
+            return false
+        }
+    };
+    info!("Confirmation reply: {:?}", reply);
+
+    let words = &DB.get().unwrap().confirmation;
+    let said = |alternative: &str, word: &String| format!(" {} ", alternative).contains(&format!(" {} ", word.to_lowercase()));
+
+    // most likely alternative decides
+    for alternative in &reply {
+        if words.deny.iter().any(|w| said(alternative, w)) {
+            info!("Command cancelled.");
+            return false
+        }
+
+        if words.confirm.iter().any(|w| said(alternative, w)) {
+            info!("Command confirmed.");
+            return true
+        }
+    }
+
+    info!("Reply is neither yes nor no, cancelling the command.");
+    false
+}
+
+// listen until something is said (returns filtered alternatives) or the timeout expires
+fn listen_for_reply(frame_buffer: &mut [i16], timeout: Duration) -> Option<Vec<String>> {
+    let start = SystemTime::now();
+
+    loop {
+        recorder::read_microphone(frame_buffer);
+
+        if let Some(alternatives) = stt::recognize_alternatives(frame_buffer) {
+            let reply = filter_alternatives(&alternatives);
+
+            if !reply.is_empty() {
+                return Some(reply)
+            }
+        }
+
+        match start.elapsed() {
+            Ok(elapsed) if elapsed > timeout => return None,
+            _ => ()
+        }
+    }
+}
+
+// play given phrase of the current voice, or some greet phrase if the voice doesn't have it
+fn play_phrase(sounds_directory: &Path, phrase: &str) {
+    let sound = sounds_directory.join(format!("{}.wav", phrase));
+
+    if sound.exists() {
+        audio::play_sound(&sound);
+    } else {
+        warn!("Voice has no {} phrase, playing greet phrase instead.", phrase);
+        audio::play_sound(&sounds_directory.join(format!("{}.wav", config::ASSISTANT_GREET_PHRASES.choose(&mut rand::thread_rng()).unwrap())));
+    }
+}
+
+fn filter_alternatives(alternatives: &[stt::Alternative]) -> Vec<String> {
+    let mut out: Vec<String> = vec![];
+
+    // confidence is a log-likelihood, alternatives far behind the best one are mostly noise
+    let best = alternatives.iter().map(|a| a.confidence).fold(f32::NEG_INFINITY, f32::max);
+
+    for alternative in alternatives.iter().filter(|a| best - a.confidence <= config::VOSK_MAX_CONFIDENCE_GAP) {
+        let filtered = filter_recognized_voice(&alternative.text);
+
+        if !filtered.is_empty() && !out.contains(&filtered) {
+            out.push(filtered);
+        }
+    }
+
+    out
+}
+
+fn filter_recognized_voice(recognized_voice: &str) -> String {
+    // @TODO. Better recognized voice filtration.
+    let mut recognized_voice = recognized_voice.to_lowercase();
+    for tbr in config::ASSISTANT_PHRASES_TBR {
+        recognized_voice = recognized_voice.replace(tbr, "");
+    }
+
+    // normalize recognized voice (number words to digits etc.)
+    normalizer::normalize(recognized_voice.trim())
+}
+
+// run the command bound to the wake-word
+fn run_wake_word_action(id: &str, commands_list: &[AssistantCommand], frame_buffer: &mut [i16], sounds_directory: &Path, dry_run: bool) {
+    let (cmd_path, cmd_config) = match commands::find_by_id(id, commands_list) {
+        Some((_, cmd_config)) if !commands::is_enabled(cmd_config) => {
+            warn!("Wake-word action {} is disabled.", id);
+            return
+        },
+        Some(command) => command,
+        None => {
+            error!("Wake-word action {:?} is not a known command id.", id);
+            return
+        }
+    };
+
+    if !confirmed(cmd_config, commands_list, frame_buffer, sounds_directory, dry_run) {
+        return
+    }
+
+    if dry_run {
+        info!("Dry run, would execute {} ({:?})", id, cmd_config.command);
+        return
+    }
+
+    info!("Running wake-word action {}.", id);
+    if let Err(msg) = commands::execute_command(cmd_path, cmd_config, &Slots::new()) {
+        error!("Error executing command: {}", msg);
+    }
 }
 
 pub fn close(code: i32) {
     info!("Closing application.");
+
+    // don't leave timed processes spawned by commands behind
+    commands::supervisor::stop_timed();
+
     std::process::exit(code);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alternative(text: &str, confidence: f32) -> stt::Alternative {
+        stt::Alternative { text: text.into(), confidence }
+    }
+
+    #[test]
+    fn drops_unlikely_alternatives() {
+        normalizer::init().unwrap();
+
+        let alternatives = [
+            alternative("джарвис открой браузер", 230.),
+            alternative("открой браузер", 228.5),
+            alternative("открой браузер сэр", 226.),
+            alternative("открой бразер", 210.),
+        ];
+
+        assert_eq!(filter_alternatives(&alternatives), vec!["открой браузер"]);
+        assert_eq!(filter_alternatives(&alternatives[2..]), vec!["открой браузер"]);
+        assert_eq!(filter_alternatives(&alternatives[3..]), vec!["открой бразер"]);
+    }
+}
@@ -1,111 +1,389 @@
 use rand::seq::SliceRandom;
-use seqdiff::ratio;
 use serde_yaml;
 use std::path::Path;
 use std::{fs, fs::File};
 
-use core::time::Duration;
 use std::path::PathBuf;
-use std::process::{Command, Child};
+use std::sync::Arc;
+use std::io::Read;
+use std::process::{Command, Child, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime};
 // use tauri::Manager;
 
 mod structs;
 pub use structs::*;
 
-use crate::{config, audio};
+mod slots;
+pub use slots::Slots;
+
+mod scorer;
+
+pub mod supervisor;
+
+mod platform;
+
+mod executors;
+pub use executors::Executor;
+
+pub mod watcher;
+
+pub mod lint;
+
+use crate::{config, audio, stt, listener, normalizer, COMMANDS_LIST, DB};
 
-// @TODO. Allow commands both in yaml and json format.
 pub fn parse_commands() -> Result<Vec<AssistantCommand>, String> {
     // collect commands
+    let (commands, errors, _) = parse_commands_dir(Path::new(config::COMMANDS_PATH))?;
+
+    for (cc_file, msg) in errors {
+        warn!("Can't parse {}, skipping ...\nCommand parse error is: {}", cc_file.display(), msg);
+    }
+
+    if commands.len() > 0 {
+        Ok(commands)
+    } else {
+        error!("No commands were found");
+        Err("No commands were found".into())
+    }
+}
+
+// parsed commands, files that failed to parse and warnings for the linter (skipped commands, missing ids)
+pub type ParsedCommands = (Vec<AssistantCommand>, Vec<(PathBuf, String)>, Vec<(PathBuf, String)>);
+
+// parse every command folder in the given directory
+pub fn parse_commands_dir(dir: &Path) -> Result<ParsedCommands, String> {
     let mut commands: Vec<AssistantCommand> = vec![];
+    let mut errors: Vec<(PathBuf, String)> = vec![];
+    let mut warnings: Vec<(PathBuf, String)> = vec![];
+    let mut skipped_ids: Vec<String> = vec![];
 
     // read commands directories first
-    if let Ok(cpaths) = fs::read_dir(config::COMMANDS_PATH) {
+    if let Ok(cpaths) = fs::read_dir(dir) {
         for cpath in cpaths {
             // validate this command, check if required files exists
             let _cpath = cpath.unwrap().path();
-            let cc_file = Path::new(&_cpath).join("command.yaml");
 
-            if cc_file.exists() {
-                // try parse config files
-                let cc_reader = std::fs::File::open(&cc_file).unwrap();
-                let cc_yaml: CommandsList;
+            // find command files, in order of precedence
+            let mut cc_files = config::COMMAND_FILE_NAMES
+                .iter()
+                .map(|f| Path::new(&_cpath).join(f))
+                .filter(|f| f.exists());
+
+            if let Some(cc_file) = cc_files.next() {
+                for lost_file in cc_files {
+                    warn!("Both {} and {} exist, ignoring {}.", cc_file.display(), lost_file.display(), lost_file.display());
+                }
+
+                // try parse command file
+                match parse_command_file(&cc_file) {
+                    Ok(mut cc_list) => {
+                        // commands without id are named after the folder and their first phrase
+                        let folder = _cpath.file_name().unwrap().to_string_lossy().to_string();
+                        for (i, scmd) in cc_list.list.iter_mut().enumerate() {
+                            if scmd.id.is_empty() {
+                                scmd.id = fallback_id(&folder, i, scmd);
+                                warnings.push((cc_file.clone(), format!("command #{} has no id, using {}", i, scmd.id)));
+                            }
+                        }
+
+                        cc_list.list.iter_mut().for_each(normalize_phrases);
+
+                        // keep commands, that can run on this os
+                        cc_list.list.retain_mut(|scmd| {
+                            let supported = platform::resolve(&mut scmd.command);
+                            if !supported {
+                                info!("Skipping command {}, it can't run on {}.", scmd.id, platform::current());
+                                warnings.push((cc_file.clone(), format!("command {} is skipped, it can't run on {}", scmd.id, platform::current())));
+                                skipped_ids.push(scmd.id.clone());
+                            }
 
-                // try parse command.yaml
-                match serde_yaml::from_reader::<File, CommandsList>(cc_reader) {
-                    Ok(parse_result) => {
-                        cc_yaml = parse_result;
+                            supported
+                        });
+
+                        // everything seems to be Ok
+                        commands.push(AssistantCommand {
+                            path: _cpath,
+                            commands: cc_list,
+                        });
                     },
                     Err(msg) => {
-                        warn!("Can't parse {}, skipping ...\nCommand parse error is: {:?}", &cc_file.display(), msg);
-                        continue;
+                        errors.push((cc_file, msg));
                     }
                 }
-                // everything seems to be Ok
-                commands.push(AssistantCommand {
-                    path: _cpath,
-                    commands: cc_yaml,
-                });
             }
         }
 
-        if commands.len() > 0 {
-            Ok(commands)
-        } else {
-            error!("No commands were found");
-            Err("No commands were found".into())
+        // sequences can't run without the skipped commands they refer to (nor the sequences running those)
+        loop {
+            let mut dropped: Vec<String> = vec![];
+
+            for cmd in commands.iter_mut() {
+                cmd.commands.list.retain(|scmd| match runs_skipped(&scmd.command, &skipped_ids) {
+                    Some(id) => {
+                        info!("Skipping command {}, it runs {}, which can't run on {}.", scmd.id, id, platform::current());
+                        warnings.push((cmd.path.clone(), format!("command {} is skipped, it runs {}, which can't run on {}", scmd.id, id, platform::current())));
+                        dropped.push(scmd.id.clone());
+                        false
+                    },
+                    None => true
+                });
+            }
+
+            if dropped.is_empty() {
+                break
+            }
+
+            skipped_ids.extend(dropped);
         }
+
+        commands.retain(|cmd| {
+            if cmd.commands.list.is_empty() {
+                info!("Skipping {}, none of its commands run on {}.", cmd.path.display(), platform::current());
+            }
+
+            !cmd.commands.list.is_empty()
+        });
+
+        Ok((commands, errors, warnings))
     } else {
         error!("Error reading commands directory");
         return Err("Error reading commands directory".into());
     }
 }
 
+// id of the command, that doesn't set one
+// it doesn't change when commands are added or reordered, unlike the index
+fn fallback_id(folder: &str, i: usize, scmd: &Config) -> String {
+    let words = scmd.phrases
+        .first()
+        .map(|phrase| phrase.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect::<Vec<_>>().join("_"))
+        .unwrap_or_default();
+
+    if words.is_empty() {
+        format!("{}.{}", folder, i)
+    } else {
+        format!("{}.{}", folder, words.to_lowercase())
+    }
+}
+
+// id of the skipped command, the sequence runs directly
+fn runs_skipped<'a>(action: &'a ConfigCommandSection, skipped_ids: &[String]) -> Option<&'a str> {
+    let action = match action {
+        ConfigCommandSection::Sequence(action) => action,
+        _ => return None
+    };
+
+    action.steps.iter().find_map(|step| match step {
+        SequenceStep::Command { command } if skipped_ids.contains(command) => Some(command.as_str()),
+        SequenceStep::Action(action) => runs_skipped(action, skipped_ids),
+        _ => None
+    })
+}
+
+// phrases are matched against normalized voice, so they must be in the same form ("сто" -> "100")
+fn normalize_phrases(scmd: &mut Config) {
+    for phrase in scmd.phrases.iter_mut().chain(scmd.exclude_phrases.iter_mut()).chain(scmd.required_words.iter_mut()) {
+        *phrase = normalizer::normalize(phrase);
+    }
+}
+
+// parse & validate commands, without touching the active ones
+// unlike parse_commands, any broken command file fails the whole set
+pub fn reparse_commands() -> Result<Vec<AssistantCommand>, String> {
+    let (commands, errors, _) = parse_commands_dir(Path::new(config::COMMANDS_PATH))?;
+
+    if let Some((cc_file, msg)) = errors.first() {
+        return Err(format!("Can't parse {} ({})", cc_file.display(), msg))
+    }
+
+    validate(&commands)?;
+
+    Ok(commands)
+}
+
+pub fn validate(commands: &[AssistantCommand]) -> Result<(), String> {
+    if commands.is_empty() {
+        return Err("No commands were found".into())
+    }
+
+    for cmd in commands {
+        for scmd in &cmd.commands.list {
+            if scmd.phrases.is_empty() {
+                return Err(format!("Command {} has no phrases", scmd.id))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// make given commands active, between utterances
+pub fn replace_commands(commands: Vec<AssistantCommand>) {
+    update_grammar(&commands);
+    COMMANDS_LIST.get().unwrap().store(Arc::new(commands));
+
+    info!("Commands reloaded: {:?}", list(&COMMANDS_LIST.get().unwrap().load()));
+}
+
+// parse command.yaml, command.json or command.toml (same schema)
+fn parse_command_file(cc_file: &Path) -> Result<CommandsList, String> {
+    match cc_file.extension().and_then(|x| x.to_str()) {
+        Some("yaml") => {
+            let cc_reader = File::open(cc_file).map_err(|e| e.to_string())?;
+            serde_yaml::from_reader::<File, CommandsList>(cc_reader).map_err(|e| format!("{:?}", e))
+        },
+        Some("json") => {
+            let cc_reader = File::open(cc_file).map_err(|e| e.to_string())?;
+            serde_json::from_reader::<File, CommandsList>(cc_reader).map_err(|e| format!("{:?}", e))
+        },
+        Some("toml") => {
+            let cc_contents = fs::read_to_string(cc_file).map_err(|e| e.to_string())?;
+            toml::from_str::<CommandsList>(&cc_contents).map_err(|e| format!("{:?}", e))
+        },
+        _ => Err(format!("Unsupported command file format: {}", cc_file.display()))
+    }
+}
+
 // @TODO. NLU or smthng else is required, in order to infer commands with highest accuracy possible.
+// `wake_word` - the one assistant was woken with, may restrict the commands
 pub fn fetch_command<'a>(
-    phrase: &str,
+    phrases: &[String],
     commands: &'a Vec<AssistantCommand>,
-) -> Option<(&'a PathBuf, &'a Config)> {
-    // result scmd
-    let mut result_scmd: Option<(&PathBuf, &Config)> = None;
-    let mut current_max_ratio = config::CMD_RATIO_THRESHOLD;
+    wake_word: &str,
+) -> FetchResult<'a> {
+    let candidates = score_candidates(phrases, commands, wake_word);
+
+    // debug info
+    for candidate in candidates.iter().take(config::CMD_DEBUG_CANDIDATES) {
+        debug!("Candidate {} ({:?}), alternative #{}, score is: {}", candidate.config.id, candidate.phrase, candidate.alternative, candidate.score);
+    }
 
-    // convert fetch phrase to sequence
-    let fetch_phrase_chars = phrase.chars().collect::<Vec<_>>();
+    let top_score = match candidates.first() {
+        Some(candidate) if candidate.score >= config::CMD_RATIO_THRESHOLD => candidate.score,
+        _ => return FetchResult::NotFound
+    };
 
-    // list all the commands
-    for cmd in commands {
-        // list all subcommands
-        for scmd in &cmd.commands.list {
-            // list all phrases in command
-            for cmd_phrase in &scmd.phrases {
-                // convert cmd phrase to sequence
-                let cmd_phrase_chars = cmd_phrase.chars().collect::<Vec<_>>();
-
-                // compare fetch phrase with cmd phrase
-                let ratio = ratio(&fetch_phrase_chars, &cmd_phrase_chars);
-
-                // return, if it fits the given threshold
-                if ratio >= current_max_ratio {
-                    result_scmd = Some((&cmd.path, &scmd));
-                    current_max_ratio = ratio;
-                    // println!("Ratio is: {}", ratio);
-                    // return Some((&cmd.path, &scmd))
+    // candidates from other command folders, that are too close to the top one
+    let margin = DB.get().unwrap().ambiguity_margin;
+    let mut options: Vec<Candidate> = vec![];
+    for candidate in candidates {
+        if options.len() >= config::CMD_AMBIGUITY_MAX_OPTIONS
+            || candidate.score < config::CMD_RATIO_THRESHOLD
+            || top_score - candidate.score > margin {
+            break
+        }
+
+        if !options.iter().any(|o| o.path == candidate.path) {
+            options.push(candidate);
+        }
+    }
+
+    if options.len() > 1 {
+        info!("Ambiguous command, {} candidates within {} points: {:?}", options.len(), margin, options.iter().map(|o| (&o.config.id, o.score)).collect::<Vec<_>>());
+        return FetchResult::Ambiguous(options)
+    }
+
+    let candidate = options.remove(0);
+    println!("Ratio is: {}", candidate.score);
+    info!("Alternative #{} chosen: {}", candidate.alternative, phrases[candidate.alternative]);
+    info!("CMD is: {}, SCMD is: {:?}, Slots are: {:?}, Ratio is: {}", candidate.config.id, candidate.config, candidate.slots, candidate.score);
+    FetchResult::Found(candidate)
+}
+
+// index of the option picked by the reply to the clarifying question
+// (ordinals are already converted to digits by the normalizer, "второй" -> "2")
+pub fn clarify_choice(reply: &[String], options: usize) -> Option<usize> {
+    reply.iter()
+        .flat_map(|alternative| alternative.split_whitespace())
+        .filter_map(|word| word.parse::<usize>().ok())
+        .find(|choice| (1..=options).contains(choice))
+        .map(|choice| choice - 1)
+}
+
+// best score of every command over all the alternatives, sorted by score
+// higher ranked alternatives win ties
+pub fn score_candidates<'a>(
+    phrases: &[String],
+    commands: &'a Vec<AssistantCommand>,
+    wake_word: &str,
+) -> Vec<Candidate<'a>> {
+    let mut candidates: Vec<Candidate> = vec![];
+
+    for (i, phrase) in phrases.iter().enumerate() {
+        // list all the commands
+        for cmd in commands {
+            // list all subcommands
+            for scmd in cmd.commands.list.iter().filter(|scmd| is_enabled(scmd) && is_allowed(scmd, wake_word)) {
+                // best phrase of this subcommand
+                let (score, cmd_phrase, slots) = match score_config(phrase, scmd) {
+                    Some(result) => result,
+                    None => continue
+                };
+
+                // keep the best score per subcommand
+                match candidates.iter_mut().find(|c| std::ptr::eq(c.config, scmd)) {
+                    Some(candidate) if score > candidate.score => {
+                        *candidate = Candidate { path: &cmd.path, config: scmd, phrase: cmd_phrase, alternative: i, slots, score };
+                    },
+                    Some(_) => (),
+                    None => {
+                        candidates.push(Candidate { path: &cmd.path, config: scmd, phrase: cmd_phrase, alternative: i, slots, score });
+                    }
                 }
             }
         }
     }
 
-    if let Some((cmd_path, scmd)) = result_scmd {
-        println!("Ratio is: {}", current_max_ratio);
-        info!("CMD is: {cmd_path:?}, SCMD is: {scmd:?}, Ratio is: {}", current_max_ratio);
-        Some((&cmd_path, &scmd))
-    } else {
-        None
+    // stable sort keeps the original order for equal scores
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    candidates
+}
+
+fn score_config<'a>(phrase: &str, scmd: &'a Config) -> Option<(f64, &'a str, Slots)> {
+    let mut result: Option<(f64, &str, Slots)> = None;
+
+    // list all phrases in command
+    for cmd_phrase in &scmd.phrases {
+        // compare fetch phrase with cmd phrase (or filled phrase template)
+        let (score, slots) = if slots::is_template(cmd_phrase) {
+            match slots::match_template(cmd_phrase, phrase, &scmd.slots) {
+                Some((rendered, slots)) => (scorer::score(phrase, &rendered), slots),
+                None => continue
+            }
+        } else {
+            (scorer::score(phrase, cmd_phrase), Slots::new())
+        };
+
+        if score > 0. && result.as_ref().is_none_or(|(s, _, _)| score > *s) {
+            result = Some((score, cmd_phrase.as_str(), slots));
+        }
+    }
+
+    let (score, cmd_phrase, slots) = result?;
+
+    // per-command threshold
+    if score < scmd.min_ratio.unwrap_or(0.) {
+        return None
+    }
+
+    // every required word must be said
+    if let Some(word) = scmd.required_words.iter().find(|w| !scorer::contains_word(phrase, w)) {
+        debug!("Skipping {:?}, required word {:?} is missing.", cmd_phrase, word);
+        return None
+    }
+
+    // phrase is closer to one of the excluded ones
+    for exclude_phrase in &scmd.exclude_phrases {
+        if scorer::score(phrase, exclude_phrase) >= score {
+            debug!("Skipping {:?}, phrase matches excluded {:?}.", cmd_phrase, exclude_phrase);
+            return None
+        }
     }
+
+    Some((score, cmd_phrase, slots))
 }
 
-// @TODO. Rewrite executors by executor type struct. (with match arms)
 pub fn execute_exe(exe: &str, args: &Vec<String>) -> std::io::Result<Child> {
     Command::new(exe).args(args).spawn()
 }
@@ -114,112 +392,641 @@ pub fn execute_cli(cmd: &str, args: &Vec<String>) -> std::io::Result<Child> {
 
     println!("Spawning cmd as: cmd /C {} {:?}", cmd, args);
 
-    if cfg!(target_os = "windows") {
-        Command::new("cmd")
-                .arg("/C")
-                .arg(cmd)
-                .args(args)
-                .spawn()
+    cli_command(cmd, args).spawn()
+}
+
+// run shell command and wait for it to finish, capturing its output
+pub fn execute_cli_output(cmd: &str, args: &Vec<String>, timeout: Duration) -> Result<CliOutput, String> {
+    info!("Running cmd and waiting for it (timeout {:?}): {} {:?}", timeout, cmd, args);
+
+    let mut child = cli_command(cmd, args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|msg| format!("Shell command error ({})", msg))?;
+
+    // read pipes in background, so the process doesn't block on a full pipe
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let start = SystemTime::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => (),
+            Err(msg) => return Err(format!("Shell command error ({})", msg))
+        }
+
+        match start.elapsed() {
+            Ok(elapsed) if elapsed > timeout => {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(format!("Shell command timed out after {:?}", timeout))
+            },
+            _ => thread::sleep(config::CLI_POLL_INTERVAL)
+        }
+    };
+
+    Ok(CliOutput {
+        code: status.code(),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn cli_command(cmd: &str, args: &Vec<String>) -> Command {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
     } else {
-        Command::new("sh")
-                .arg("-c")
-                .arg(cmd)
-                .args(args)
-                .spawn()
-    }
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+
+    command.arg(cmd).args(args);
+    command
+}
+
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut out: Vec<u8> = vec![];
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut out).ok();
+        }
+
+        String::from_utf8_lossy(&out).trim().to_string()
+    })
 }
 
 pub fn execute_command(
-    cmd_path: &PathBuf,
+    cmd_path: &Path,
     cmd_config: &Config,
+    slots: &Slots,
     // app_handle: &tauri::AppHandle,
 ) -> Result<bool, String> {
     let sounds_directory = audio::get_sound_directory().unwrap();
+    let executor = cmd_config.command.executor();
 
-    match cmd_config.command.action.as_str() {
-        "voice" => {
-            // VOICE command type
-            let random_cmd_sound = format!("{}.wav", cmd_config.voice.sounds.choose(&mut rand::thread_rng()).unwrap());
-            // events::play(random_cmd_sound, app_handle);
-            audio::play_sound(&sounds_directory.join(random_cmd_sound));
-
-            Ok(true)
-        }
-        "ahk" => {
-            // AutoHotkey command type
-            let exe_path_absolute = Path::new(&cmd_config.command.exe_path);
-            let exe_path_local = Path::new(&cmd_path).join(&cmd_config.command.exe_path);
-
-            if let Ok(_) = execute_exe(
-                if exe_path_absolute.exists() {
-                    exe_path_absolute.to_str().unwrap()
-                } else {
-                    exe_path_local.to_str().unwrap()
-                },
-                &cmd_config.command.exe_args,
-            ) {
-                let random_cmd_sound = format!("{}.wav", cmd_config.voice.sounds.choose(&mut rand::thread_rng()).unwrap());
-                // events::play(random_cmd_sound, app_handle);
-                audio::play_sound(&sounds_directory.join(random_cmd_sound));
-
-                Ok(true)
-            } else {
-                error!("AHK process spawn error (does exe path is valid?)");
-                Err("AHK process spawn error (does exe path is valid?)".into())
+    // run the action itself
+    let result = executor.execute(cmd_path, slots);
+
+    // play some confirmation (or failure) sound
+    let sounds = if result.is_ok() { &cmd_config.voice.sounds } else { &cmd_config.voice.failure_sounds };
+    if let Some(sound) = sounds.choose(&mut rand::thread_rng()) {
+        // events::play(random_cmd_sound, app_handle);
+        audio::play_sound(&sounds_directory.join(format!("{}.wav", sound)));
+    }
+
+    let chain = result?;
+    executor.finish();
+
+    Ok(chain)
+}
+
+pub fn find_by_id<'a>(id: &str, from: &'a [AssistantCommand]) -> Option<(&'a PathBuf, &'a Config)> {
+    for cmd in from.iter() {
+        for scmd in &cmd.commands.list {
+            if scmd.id == id {
+                return Some((&cmd.path, scmd))
             }
         }
-        "cli" => {
-            // CLI command type
-            let cli_cmd = &cmd_config.command.cli_cmd;
+    }
 
-            match execute_cli(
-                cli_cmd,
-                &cmd_config.command.cli_args,
-            ) {
-                    Ok(_) => {
-                        let random_cmd_sound = format!("{}.wav", cmd_config.voice.sounds.choose(&mut rand::thread_rng()).unwrap());
-                    // events::play(random_cmd_sound, app_handle);
-                        audio::play_sound(&sounds_directory.join(random_cmd_sound));
+    None
+}
 
-                    Ok(true)
-                },
-                Err(msg) => {
-                    error!("CLI command error ({})", msg);
-                    Err(format!("Shell command error ({})", msg).into())
-                }
+// whether the command must be confirmed, either itself or some command its sequence runs
+// (sequence steps can't ask, so the whole sequence is confirmed up front)
+pub fn needs_confirmation(scmd: &Config, from: &[AssistantCommand]) -> bool {
+    scmd.confirm || runs_confirmed(&scmd.command, from, &mut vec![])
+}
+
+fn runs_confirmed<'a>(action: &'a ConfigCommandSection, from: &'a [AssistantCommand], visited: &mut Vec<&'a str>) -> bool {
+    let action = match action {
+        ConfigCommandSection::Sequence(action) => action,
+        _ => return false
+    };
+
+    action.steps.iter().any(|step| match step {
+        SequenceStep::Command { command } if !visited.contains(&command.as_str()) => {
+            visited.push(command);
+
+            match find_by_id(command, from) {
+                Some((_, scmd)) => scmd.confirm || runs_confirmed(&scmd.command, from, visited),
+                None => false
             }
-        }
-        "terminate" => {
-            // TERMINATE command type
-            let random_cmd_sound = format!("{}.wav", cmd_config.voice.sounds.choose(&mut rand::thread_rng()).unwrap());
-            // events::play(random_cmd_sound, app_handle);
-            audio::play_sound(&sounds_directory.join(random_cmd_sound));
+        },
+        SequenceStep::Action(action) => runs_confirmed(action, from, visited),
+        _ => false
+    })
+}
 
-            std::thread::sleep(Duration::from_secs(2));
-            std::process::exit(0);
-        }
-        "stop_chaining" => {
-            // STOP_CHAINING command type
-            let random_cmd_sound = format!("{}.wav", cmd_config.voice.sounds.choose(&mut rand::thread_rng()).unwrap());
-            // events::play(random_cmd_sound, app_handle);
-            audio::play_sound(&sounds_directory.join(random_cmd_sound));
+// disabled either in the command file or in settings
+pub fn is_enabled(scmd: &Config) -> bool {
+    scmd.enabled && !DB.get().unwrap().disabled_commands.contains(&scmd.id)
+}
 
-            Ok(false)
-        }
-        _ => {
-            error!("Command type unknown");
-            Err("Command type unknown".into())
+// whether the command can be run after the given wake-word
+pub fn is_allowed(scmd: &Config, wake_word: &str) -> bool {
+    match listener::behaviour(wake_word) {
+        Some(behaviour) if !behaviour.commands.is_empty() => {
+            behaviour.commands.iter().any(|x| *x == scmd.id || scmd.tags.contains(x))
         },
+        _ => true
     }
 }
 
+// all the commands, as described by `describe`
 pub fn list(from: &[AssistantCommand]) -> Vec<String> {
     let mut out: Vec<String> = vec![];
 
     for x in from.iter() {
-        out.push(String::from(x.path.to_str().unwrap()));
-        // out.append()
+        for scmd in &x.commands.list {
+            out.push(describe(scmd));
+        }
+    }
+
+    out
+}
+
+// id, with version and description (if set)
+pub fn describe(scmd: &Config) -> String {
+    let mut out = scmd.id.clone();
+
+    if let Some(version) = &scmd.version {
+        out.push_str(&format!(" v{}", version));
+    }
+
+    if let Some(description) = &scmd.description {
+        out.push_str(&format!(" - {}", description));
+    }
+
+    out
+}
+
+// every word commands can be spoken with (used to build stt grammar)
+pub fn vocabulary(from: &[AssistantCommand]) -> Vec<String> {
+    let mut out: Vec<String> = vec![];
+
+    for cmd in from.iter() {
+        for scmd in &cmd.commands.list {
+            for phrase in &scmd.phrases {
+                for word in slots::vocabulary(phrase, &scmd.slots) {
+                    if !out.contains(&word) {
+                        out.push(word);
+                    }
+                }
+            }
+        }
     }
 
     out
-}
\ No newline at end of file
+}
+
+// rebuild stt grammar, should be called every time commands are (re)loaded
+pub fn update_grammar(from: &[AssistantCommand]) {
+    if !stt::grammar_enabled() {
+        return // free-form recognition, nothing to do
+    }
+
+    let mut words = vocabulary(from);
+    for tbr in config::ASSISTANT_PHRASES_TBR {
+        words.extend(tbr.split_whitespace().map(String::from));
+    }
+    words.push(config::VOSK_FETCH_PHRASE.into());
+
+    // replies to the clarifying question
+    words.extend(config::ASSISTANT_CLARIFY_WORDS.iter().map(|x| String::from(*x)));
+
+    // replies to the confirmation prompt
+    let confirmation = &DB.get().unwrap().confirmation;
+    for word in confirmation.confirm.iter().chain(confirmation.deny.iter()) {
+        words.extend(word.to_lowercase().split_whitespace().map(String::from));
+    }
+
+    words.sort();
+    words.dedup();
+
+    if stt::set_grammar(&words).is_err() {
+        warn!("Cannot build stt grammar, keeping the previous recognizer.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        DB.get_or_init(crate::db::structs::Settings::default);
+        normalizer::init().unwrap();
+    }
+
+    // shipped command pack, normalized the same way as while parsing
+    fn shipped_command(folder: &str) -> AssistantCommand {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(config::COMMANDS_PATH).join(folder);
+        let mut cc_list = parse_command_file(&path.join("command.yaml")).unwrap();
+        cc_list.list.iter_mut().for_each(normalize_phrases);
+
+        AssistantCommand { path, commands: cc_list }
+    }
+
+    fn parse_yaml(yaml: &str) -> Result<CommandsList, serde_yaml::Error> {
+        serde_yaml::from_str::<CommandsList>(yaml)
+    }
+
+    #[test]
+    fn parses_shipped_commands() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(config::COMMANDS_PATH);
+        let mut parsed = 0;
+
+        for cpath in fs::read_dir(&dir).unwrap() {
+            let cpath = cpath.unwrap().path();
+            let cc_file = config::COMMAND_FILE_NAMES.iter().map(|f| cpath.join(f)).find(|f| f.exists());
+
+            if let Some(cc_file) = cc_file {
+                let cc_list = parse_command_file(&cc_file).unwrap_or_else(|msg| panic!("{}: {}", cc_file.display(), msg));
+                assert!(!cc_list.list.is_empty(), "{}: no commands", cc_file.display());
+                parsed += 1;
+            }
+        }
+
+        assert!(parsed > 0);
+    }
+
+    #[test]
+    fn parses_lenient_string_lists() {
+        let cc_list = parse_yaml("
+list:
+- command:
+    action: ahk
+    exe_path: a.exe
+    exe_args:
+  voice: {}
+  phrases: [a]
+- command:
+    action: exe
+    exe_path: b.exe
+    exe_args: [25, 0.5, true, x]
+    linux:
+      exe_args:
+      - 100
+  voice: {}
+  phrases: [b]
+- command:
+    action: sequence
+    steps:
+    - action: cli
+      cli_cmd: c
+      cli_args:
+  voice: {}
+  phrases: [c]
+").unwrap();
+
+        match &cc_list.list[0].command {
+            ConfigCommandSection::Ahk(action) => assert!(action.exe_args.is_empty()),
+            other => panic!("unexpected action {:?}", other)
+        }
+
+        match &cc_list.list[1].command {
+            ConfigCommandSection::Exe(action) => {
+                assert_eq!(action.exe_args, vec!["25", "0.5", "true", "x"]);
+                assert_eq!(action.linux.as_ref().unwrap().exe_args, Some(vec![String::from("100")]));
+                assert!(action.windows.is_none());
+            },
+            other => panic!("unexpected action {:?}", other)
+        }
+
+        match &cc_list.list[2].command {
+            ConfigCommandSection::Sequence(action) => match &action.steps[0] {
+                SequenceStep::Action(ConfigCommandSection::Cli(action)) => assert!(action.cli_args.is_empty()),
+                other => panic!("unexpected step {:?}", other)
+            },
+            other => panic!("unexpected action {:?}", other)
+        }
+    }
+
+    #[test]
+    fn matches_number_words_in_phrases() {
+        init();
+        let commands = vec![shipped_command("volume")];
+
+        let table = [
+            ("поставь громкость на сто", "100"),
+            ("звук сто", "100"),
+            ("поставь громкость на пятьдесят", "50"),
+            ("звук пятьдесят", "50"),
+        ];
+
+        for (phrase, level) in table {
+            match fetch_command(&[normalizer::normalize(phrase)], &commands, "") {
+                FetchResult::Found(Candidate { config: Config { command: ConfigCommandSection::Ahk(action), .. }, .. }) => {
+                    assert_eq!(action.exe_args, vec![level], "phrase: {}", phrase);
+                },
+                other => panic!("phrase: {}, unexpected result {:?}", phrase, other)
+            }
+        }
+    }
+
+    #[test]
+    fn confirms_sequences_running_confirmed_commands() {
+        let commands = vec![AssistantCommand { path: PathBuf::from("test"), commands: parse_yaml("
+list:
+- id: reboot
+  confirm: true
+  command: {action: voice}
+  voice: {}
+  phrases: [a]
+- id: sequence
+  command:
+    action: sequence
+    steps:
+    - delay: 100
+    - command: reboot
+  voice: {}
+  phrases: [b]
+- id: nested
+  command:
+    action: sequence
+    steps:
+    - action: sequence
+      steps:
+      - command: sequence
+  voice: {}
+  phrases: [c]
+- id: cycle
+  command:
+    action: sequence
+    steps:
+    - command: cycle
+    - command: unknown
+  voice: {}
+  phrases: [d]
+").unwrap() }];
+
+        let needs = |id: &str| needs_confirmation(find_by_id(id, &commands).unwrap().1, &commands);
+
+        assert!(needs("reboot"));
+        assert!(needs("sequence"));
+        assert!(needs("nested"));
+        assert!(!needs("cycle"));
+    }
+
+    #[test]
+    fn describes_commands() {
+        let cc_list = parse_yaml("
+list:
+- id: plain
+  command: {action: voice}
+  voice: {}
+  phrases: [a]
+- id: described
+  description: Opens the browser
+  version: '1.2'
+  command: {action: voice}
+  voice: {}
+  phrases: [b]
+").unwrap();
+
+        assert_eq!(describe(&cc_list.list[0]), "plain");
+        assert_eq!(describe(&cc_list.list[1]), "described v1.2 - Opens the browser");
+    }
+
+    #[test]
+    fn resolves_ambiguous_commands() {
+        init();
+
+        let folder = |name: &str, yaml: &str| AssistantCommand { path: PathBuf::from(name), commands: parse_yaml(yaml).unwrap() };
+        let commands = vec![
+            folder("a", "
+list:
+- id: a.mail
+  command: {action: voice}
+  voice: {}
+  phrases: [открой почту, проверь почту]
+- id: a.mail.check
+  command: {action: voice}
+  voice: {}
+  phrases: [проверь почту]
+"),
+            folder("b", "
+list:
+- id: b.mail
+  command: {action: voice}
+  voice: {}
+  phrases: [открой почту]
+- id: b.music.on
+  command: {action: voice}
+  voice: {}
+  phrases: [включи музыку]
+"),
+            folder("c", "
+list:
+- id: c.mail
+  command: {action: voice}
+  voice: {}
+  phrases: [открой почту]
+- id: c.music.off
+  command: {action: voice}
+  voice: {}
+  phrases: [выключи музыку]
+"),
+            folder("d", "
+list:
+- id: d.mail
+  command: {action: voice}
+  voice: {}
+  phrases: [открой почту]
+- id: d.movie
+  command: {action: voice}
+  voice: {}
+  phrases: [найди фильм онлайн]
+"),
+            folder("e", "
+list:
+- id: e.movie
+  command: {action: voice}
+  voice: {}
+  phrases: [найди фильм]
+"),
+        ];
+
+        let ids = |result: FetchResult| match result {
+            FetchResult::Found(candidate) => vec![candidate.config.id.clone()],
+            FetchResult::Ambiguous(options) => options.iter().map(|o| o.config.id.clone()).collect(),
+            FetchResult::NotFound => vec![]
+        };
+
+        let table = [
+            // one command per folder, up to CMD_AMBIGUITY_MAX_OPTIONS
+            ("открой почту", vec!["a.mail", "b.mail", "c.mail"]),
+            // equal commands from the same folder aren't ambiguous
+            ("проверь почту", vec!["a.mail"]),
+            // negated verbs aren't close
+            ("включи музыку", vec!["b.music.on"]),
+            // 80 points is out of the margin
+            ("найди фильм", vec!["e.movie"]),
+            ("найди фильм онлайн", vec!["d.movie"]),
+            ("поставь будильник", vec![]),
+        ];
+
+        for (phrase, expected) in table {
+            assert_eq!(ids(fetch_command(&[normalizer::normalize(phrase)], &commands, "")), expected, "phrase: {}", phrase);
+        }
+
+        // replies to the clarifying question, for the "открой почту" options
+        let table = [
+            ("первый", Some("a.mail")),
+            ("вторая", Some("b.mail")),
+            ("давай три", Some("c.mail")),
+            ("четвертый", None),
+            ("никакой", None),
+        ];
+
+        for (reply, expected) in table {
+            let options = match fetch_command(&[normalizer::normalize("открой почту")], &commands, "") {
+                FetchResult::Ambiguous(options) => options,
+                other => panic!("unexpected result {:?}", other)
+            };
+
+            let chosen = clarify_choice(&[normalizer::normalize(reply)], options.len()).map(|i| options[i].config.id.as_str());
+            assert_eq!(chosen, expected, "reply: {}", reply);
+        }
+    }
+
+    #[test]
+    fn filters_commands() {
+        init();
+
+        // excluded phrase isn't a candidate at all, even with a low score
+        let commands = vec![shipped_command("browser")];
+        let voice = [normalizer::normalize("закрой браузер")];
+
+        assert!(!score_candidates(&voice, &commands, "").iter().any(|c| c.config.id == "browser.open"));
+        match fetch_command(&voice, &commands, "") {
+            FetchResult::Found(candidate) => assert_ne!(candidate.config.id, "browser.open"),
+            other => panic!("unexpected result {:?}", other)
+        }
+
+        let commands = vec![AssistantCommand { path: PathBuf::from("test"), commands: parse_yaml("
+list:
+- id: mute
+  command: {action: voice}
+  voice: {}
+  phrases: [выключи звук полностью]
+  required_words: [звук]
+- id: mail
+  command: {action: voice}
+  voice: {}
+  phrases: [открой почту яндекс]
+  min_ratio: 90
+").unwrap() }];
+
+        // both would pass the common threshold
+        assert!(scorer::score("выключи полностью", "выключи звук полностью") > config::CMD_RATIO_THRESHOLD);
+        assert!(scorer::score("открой почту", "открой почту яндекс") > config::CMD_RATIO_THRESHOLD);
+
+        let table = [
+            ("выключи звук полностью", Some("mute")),
+            ("выключи звуки полностью", Some("mute")),
+            ("выключи полностью", None),
+            ("открой почту яндекс", Some("mail")),
+            ("открой почту", None),
+        ];
+
+        for (phrase, expected) in table {
+            let id = match fetch_command(&[normalizer::normalize(phrase)], &commands, "") {
+                FetchResult::Found(candidate) => Some(candidate.config.id.as_str()),
+                FetchResult::NotFound => None,
+                other => panic!("phrase: {}, unexpected result {:?}", phrase, other)
+            };
+
+            assert_eq!(id, expected, "phrase: {}", phrase);
+        }
+    }
+
+    #[test]
+    fn skips_sequences_running_skipped_commands() {
+        init();
+
+        let other_os = if platform::current() == "windows" { "linux" } else { "windows" };
+        let dir = std::env::temp_dir().join(format!("jarvis-commands-{}", std::process::id()));
+        let write = |folder: &str, yaml: &str| {
+            fs::create_dir_all(dir.join(folder)).unwrap();
+            fs::write(dir.join(folder).join("command.yaml"), yaml).unwrap();
+        };
+
+        write("app", &format!("
+list:
+- id: app.open
+  command:
+    action: exe
+    {}:
+      exe_path: app.exe
+  voice: {{}}
+  phrases: [открой приложение]
+- id: app.info
+  command: {{action: voice}}
+  voice: {{}}
+  phrases: [что за приложение]
+", other_os));
+        write("mode", "
+list:
+- id: mode.start
+  command:
+    action: sequence
+    steps:
+    - action: sequence
+      steps:
+      - command: app.open
+  voice: {}
+  phrases: [рабочий режим]
+- id: mode.all
+  command:
+    action: sequence
+    steps:
+    - command: mode.start
+  voice: {}
+  phrases: [полный рабочий режим]
+");
+        write("other", "
+list:
+- id: other.info
+  command:
+    action: sequence
+    steps:
+    - delay: 100
+    - command: app.info
+  voice: {}
+  phrases: [расскажи о приложении]
+");
+
+        let (commands, errors, warnings) = parse_commands_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut ids = commands.iter().flat_map(|c| c.commands.list.iter().map(|scmd| scmd.id.as_str())).collect::<Vec<_>>();
+        ids.sort();
+
+        assert!(errors.is_empty());
+        assert_eq!(ids, vec!["app.info", "other.info"]);
+        assert_eq!(warnings.len(), 3);
+        assert!(!commands.iter().any(|c| c.path.ends_with("mode")));
+    }
+
+    #[test]
+    fn names_commands_without_id() {
+        let cc_list = parse_yaml("
+list:
+- command: {action: voice}
+  voice: {}
+  phrases: [\"Открой {site}\", \"запусти {site}\"]
+- command: {action: voice}
+  voice: {}
+  phrases: []
+").unwrap();
+
+        assert_eq!(fallback_id("browser", 0, &cc_list.list[0]), "browser.открой_site");
+        assert_eq!(fallback_id("browser", 1, &cc_list.list[1]), "browser.1");
+    }
+}
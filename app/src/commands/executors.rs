@@ -0,0 +1,383 @@
+use core::time::Duration;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::{config, audio, tts, COMMANDS_LIST};
+use std::cell::Cell;
+use std::process::Child;
+
+use super::{slots, supervisor, Slots};
+use super::{ConfigCommandSection, VoiceAction, ExeAction, CliAction, HttpAction, TerminateAction, StopChainingAction, StopLastAction};
+use super::{SequenceAction, SequenceStep};
+
+pub trait Executor {
+    // run the action, returns whether voice recognition should continue (commands chaining)
+    fn execute(&self, cmd_path: &Path, slots: &Slots) -> Result<bool, String>;
+
+    // called after the confirmation sound was played
+    fn finish(&self) {}
+}
+
+impl ConfigCommandSection {
+    pub fn executor(&self) -> &dyn Executor {
+        match self {
+            ConfigCommandSection::Voice(action) => action,
+            ConfigCommandSection::Ahk(action) => action,
+            ConfigCommandSection::Exe(action) => action,
+            ConfigCommandSection::Cli(action) => action,
+            ConfigCommandSection::Http(action) => action,
+            ConfigCommandSection::Terminate(action) => action,
+            ConfigCommandSection::StopChaining(action) => action,
+            ConfigCommandSection::StopLast(action) => action,
+            ConfigCommandSection::Sequence(action) => action,
+        }
+    }
+}
+
+impl Executor for VoiceAction {
+    fn execute(&self, _cmd_path: &Path, _slots: &Slots) -> Result<bool, String> {
+        // nothing to do, except playing the sound
+        Ok(true)
+    }
+}
+
+impl Executor for ExeAction {
+    fn execute(&self, cmd_path: &Path, slots: &Slots) -> Result<bool, String> {
+        let key = process_key(cmd_path, &self.exe_path);
+        if self.single_instance && supervisor::is_running(&key) {
+            info!("{} is already running, skipping.", key);
+            return Ok(true)
+        }
+
+        let exe_path = slots::substitute(&self.exe_path, slots);
+        let exe_path_absolute = Path::new(&exe_path);
+        let exe_path_local = Path::new(&cmd_path).join(&exe_path);
+
+        match super::execute_exe(
+            if exe_path_absolute.exists() {
+                exe_path_absolute.to_str().unwrap()
+            } else {
+                exe_path_local.to_str().unwrap()
+            },
+            &slots::substitute_all(&self.exe_args, slots),
+        ) {
+            Ok(child) => {
+                track(&key, child, self.timeout);
+                Ok(true)
+            },
+            Err(msg) => {
+                error!("Process spawn error (does exe path is valid?)\nError details: {}", msg);
+                Err("Process spawn error (does exe path is valid?)".into())
+            }
+        }
+    }
+}
+
+impl Executor for CliAction {
+    fn execute(&self, cmd_path: &Path, slots: &Slots) -> Result<bool, String> {
+        let cli_cmd = slots::substitute_shell(&self.cli_cmd, slots);
+        let cli_args = slots::substitute_shell_args(&self.cli_args, slots);
+
+        if !self.wait {
+            let key = process_key(cmd_path, &self.cli_cmd);
+            if self.single_instance && supervisor::is_running(&key) {
+                info!("{} is already running, skipping.", key);
+                return Ok(true)
+            }
+
+            // run in background
+            return match super::execute_cli(&cli_cmd, &cli_args) {
+                Ok(child) => {
+                    track(&key, child, self.timeout);
+                    Ok(true)
+                },
+                Err(msg) => {
+                    error!("CLI command error ({})", msg);
+                    Err(format!("Shell command error ({})", msg))
+                }
+            }
+        }
+        let timeout = self.timeout.map(Duration::from_secs).unwrap_or(config::CLI_DEFAULT_TIMEOUT);
+        let output = super::execute_cli_output(&cli_cmd, &cli_args, timeout).map_err(|msg| {
+            error!("{}", msg);
+            msg
+        })?;
+
+        info!("CLI command exited with code {:?}.", output.code);
+        if !output.stdout.is_empty() {
+            info!("CLI command output:\n{}", output.stdout);
+        }
+        if !output.stderr.is_empty() {
+            warn!("CLI command error output:\n{}", output.stderr);
+        }
+
+        if self.speak_output {
+            if let Err(msg) = tts::speak(&output.stdout) {
+                warn!("Cannot speak CLI command output: {}", msg);
+            }
+        }
+
+        match output.code {
+            Some(0) => Ok(true),
+            code => Err(format!("Shell command failed with exit code {:?}", code))
+        }
+    }
+}
+
+impl Executor for HttpAction {
+    fn execute(&self, _cmd_path: &Path, slots: &Slots) -> Result<bool, String> {
+        let status = self.send(slots).map_err(|msg| {
+            error!("{}", msg);
+            msg
+        })?;
+
+        let expected = match self.expected_status {
+            Some(expected_status) => status == expected_status,
+            None => (200..300).contains(&status)
+        };
+
+        if expected {
+            info!("HTTP request succeeded with status {}.", status);
+            Ok(true)
+        } else {
+            error!("HTTP request returned unexpected status {}.", status);
+            Err(format!("HTTP request returned unexpected status {}", status))
+        }
+    }
+}
+
+impl HttpAction {
+    // send the request with slots filled in, returns response status
+    pub fn send(&self, slots: &Slots) -> Result<u16, String> {
+        let method = self.method.as_deref().unwrap_or("GET").to_uppercase();
+        let url = slots::substitute(&self.url, slots);
+        let timeout = self.timeout.map(Duration::from_secs).unwrap_or(config::HTTP_DEFAULT_TIMEOUT);
+
+        info!("Sending HTTP request: {} {}", method, url);
+
+        let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+        let mut request = agent.request(&method, &url);
+        for (name, value) in &self.headers {
+            request = request.set(name, &slots::substitute(value, slots));
+        }
+
+        let response = match &self.body {
+            Some(body) => request.send_json(substitute_json(body, slots)),
+            None => request.call()
+        };
+
+        match response {
+            Ok(response) => Ok(response.status()),
+            Err(ureq::Error::Status(status, _)) => Ok(status),
+            Err(msg) => Err(format!("HTTP request error ({})", msg))
+        }
+    }
+}
+
+// processes are told apart by the command folder and the (unfilled) executable
+fn process_key(cmd_path: &Path, exe: &str) -> String {
+    format!("{}: {}", cmd_path.display(), exe)
+}
+
+fn track(key: &str, child: Child, timeout: Option<u64>) {
+    supervisor::track(key, child, timeout.map(Duration::from_secs));
+}
+
+// fill slots in every string of the json body template
+fn substitute_json(value: &Value, slots: &Slots) -> Value {
+    match value {
+        Value::String(s) => Value::String(slots::substitute(s, slots)),
+        Value::Array(items) => Value::Array(items.iter().map(|x| substitute_json(x, slots)).collect()),
+        Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), substitute_json(v, slots))).collect()),
+        _ => value.clone()
+    }
+}
+
+impl Executor for TerminateAction {
+    fn execute(&self, _cmd_path: &Path, _slots: &Slots) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    fn finish(&self) {
+        // let the sound play, then quit
+        std::thread::sleep(Duration::from_secs(2));
+        crate::app::close(0);
+    }
+}
+
+impl Executor for StopChainingAction {
+    fn execute(&self, _cmd_path: &Path, _slots: &Slots) -> Result<bool, String> {
+        Ok(false)
+    }
+}
+
+impl Executor for StopLastAction {
+    fn execute(&self, _cmd_path: &Path, _slots: &Slots) -> Result<bool, String> {
+        supervisor::stop_last().map(|_| true).map_err(|msg| {
+            warn!("{}", msg);
+            msg
+        })
+    }
+}
+
+thread_local! {
+    // nesting of the running sequences, to break reference loops
+    static SEQUENCE_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+impl Executor for SequenceAction {
+    fn execute(&self, cmd_path: &Path, slots: &Slots) -> Result<bool, String> {
+        let depth = SEQUENCE_DEPTH.with(|d| d.get());
+        if depth >= config::SEQUENCE_MAX_DEPTH {
+            error!("Sequences are nested too deep (do they reference each other?)");
+            return Err("Sequences are nested too deep".into())
+        }
+
+        SEQUENCE_DEPTH.with(|d| d.set(depth + 1));
+        let result = self.run_steps(cmd_path, slots);
+        SEQUENCE_DEPTH.with(|d| d.set(depth));
+
+        result
+    }
+}
+
+impl SequenceAction {
+    fn run_steps(&self, cmd_path: &Path, slots: &Slots) -> Result<bool, String> {
+        let mut chain = true;
+
+        for (i, step) in self.steps.iter().enumerate() {
+            info!("Running sequence step #{}: {:?}", i, step);
+
+            match run_step(step, cmd_path, slots) {
+                Ok(step_chain) => chain &= step_chain,
+                Err(msg) if self.continue_on_failure => {
+                    warn!("Sequence step #{} failed ({}), continuing.", i, msg);
+                },
+                Err(msg) => {
+                    return Err(format!("Sequence step #{} failed ({})", i, msg))
+                }
+            }
+        }
+
+        Ok(chain)
+    }
+}
+
+fn run_step(step: &SequenceStep, cmd_path: &Path, slots: &Slots) -> Result<bool, String> {
+    match step {
+        SequenceStep::Delay { delay } => {
+            std::thread::sleep(Duration::from_millis(*delay));
+            Ok(true)
+        },
+        SequenceStep::Sound { sound } => {
+            audio::play_sound(&audio::get_sound_directory().unwrap().join(format!("{}.wav", sound)));
+            Ok(true)
+        },
+        SequenceStep::Command { command } => {
+            let commands = COMMANDS_LIST.get().unwrap().load();
+
+            match super::find_by_id(command, &commands) {
+                Some((_, scmd)) if !super::is_enabled(scmd) => Err(format!("Command '{}' is disabled", command)),
+                Some((path, scmd)) => run_action(&scmd.command, path, slots),
+                None => Err(format!("Unknown command id '{}'", command))
+            }
+        },
+        SequenceStep::Action(action) => run_action(action, cmd_path, slots)
+    }
+}
+
+// without the command sounds, sequences play them with sound steps
+fn run_action(action: &ConfigCommandSection, cmd_path: &Path, slots: &Slots) -> Result<bool, String> {
+    let executor = action.executor();
+    let chain = executor.execute(cmd_path, slots)?;
+    executor.finish();
+
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    // answers a single request with the given status, returns the raw request
+    fn stub_server(status: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request: Vec<u8> = vec![];
+            let mut buf = [0u8; 1024];
+
+            // read headers and the whole body
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(headers_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..headers_end]
+                        .lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|x| x.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+
+                    if n == 0 || request.len() >= headers_end + 4 + content_length {
+                        break
+                    }
+                }
+            }
+
+            stream.write_all(format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).as_bytes()).unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        (url, handle)
+    }
+
+    fn action(url: String, body: Option<Value>, expected_status: Option<u16>) -> HttpAction {
+        HttpAction {
+            method: Some(String::from("post")),
+            url: format!("{}/lights/{{room}}", url),
+            headers: HashMap::from([(String::from("X-Room"), String::from("{room}"))]),
+            body,
+            timeout: Some(2),
+            expected_status,
+        }
+    }
+
+    #[test]
+    fn sends_request_with_slots() {
+        let (url, server) = stub_server("200 OK");
+        let slots = Slots::from([(String::from("room"), String::from("kitchen"))]);
+        let body = serde_json::json!({"room": "{room}", "on": true});
+
+        assert_eq!(action(url, Some(body), None).execute(Path::new(""), &slots), Ok(true));
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /lights/kitchen HTTP/1.1"));
+        assert!(request.to_lowercase().contains("x-room: kitchen"));
+        assert!(request.contains(r#""room":"kitchen""#));
+    }
+
+    #[test]
+    fn fails_on_unexpected_status() {
+        let (url, server) = stub_server("500 Internal Server Error");
+
+        assert!(action(url, None, None).execute(Path::new(""), &Slots::new()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn honours_expected_status() {
+        let (url, server) = stub_server("404 Not Found");
+
+        assert_eq!(action(url, None, Some(404)).execute(Path::new(""), &Slots::new()), Ok(true));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{config, SOUND_DIR};
+use super::{slots, AssistantCommand, Config, ConfigCommandSection, SequenceStep};
+
+#[derive(Default)]
+struct Report {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Report {
+    fn error(&mut self, msg: String) {
+        println!("[ERROR] {}", msg);
+        self.errors.push(msg);
+    }
+
+    fn warn(&mut self, msg: String) {
+        println!("[WARN]  {}", msg);
+        self.warnings.push(msg);
+    }
+}
+
+// check every command in the given directory, returns exit code
+pub fn run(dir: &Path) -> i32 {
+    let mut report = Report::default();
+
+    println!("Checking commands in {} ...", dir.display());
+
+    // schema
+    let commands = match super::parse_commands_dir(dir) {
+        Ok((commands, errors, warnings)) => {
+            for (cc_file, msg) in errors {
+                report.error(format!("{}: {}", cc_file.display(), msg));
+            }
+
+            for (cc_file, msg) in warnings {
+                report.warn(format!("{}: {}", cc_file.display(), msg));
+            }
+
+            commands
+        },
+        Err(msg) => {
+            report.error(msg);
+            vec![]
+        }
+    };
+
+    if commands.is_empty() {
+        report.error(String::from("No commands were found"));
+    }
+
+    let voices = installed_voices();
+    println!("Installed voices: {:?}", voices.iter().map(|v| v.file_name().unwrap()).collect::<Vec<_>>());
+
+    for cmd in &commands {
+        for (i, scmd) in cmd.commands.list.iter().enumerate() {
+            check_config(&mut report, cmd, i, scmd, &commands, &voices);
+        }
+    }
+
+    check_duplicates(&mut report, &commands);
+    check_ids(&mut report, &commands);
+    check_collisions(&mut report, &commands);
+
+    println!("\n{} error(s), {} warning(s).", report.errors.len(), report.warnings.len());
+
+    if report.errors.is_empty() { 0 } else { 1 }
+}
+
+fn name(cmd: &AssistantCommand, i: usize) -> String {
+    format!("{} ({})", cmd.commands.list[i].id, cmd.path.display())
+}
+
+fn installed_voices() -> Vec<PathBuf> {
+    match fs::read_dir(SOUND_DIR.as_path()) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect(),
+        Err(_) => vec![]
+    }
+}
+
+// whether the executable can be found in PATH
+fn on_path(exe: &str) -> bool {
+    match std::env::var_os("PATH") {
+        Some(paths) => std::env::split_paths(&paths).any(|dir| dir.join(exe).is_file()),
+        None => false
+    }
+}
+
+fn check_config(report: &mut Report, cmd: &AssistantCommand, i: usize, scmd: &Config, commands: &[AssistantCommand], voices: &[PathBuf]) {
+    let name = name(cmd, i);
+
+    if scmd.phrases.is_empty() {
+        report.error(format!("{}: no phrases", name));
+    }
+
+    check_action(report, &name, cmd, &scmd.command, commands, voices);
+
+    // sounds
+    if scmd.voice.sounds.is_empty() {
+        report.error(format!("{}: voice.sounds is empty", name));
+    }
+
+    for sound in scmd.voice.sounds.iter().chain(scmd.voice.failure_sounds.iter()) {
+        check_sound(report, &name, sound, voices);
+    }
+}
+
+fn check_action(report: &mut Report, name: &str, cmd: &AssistantCommand, action: &ConfigCommandSection, commands: &[AssistantCommand], voices: &[PathBuf]) {
+    match action {
+        ConfigCommandSection::Ahk(action) | ConfigCommandSection::Exe(action) => {
+            let exe_path = &action.exe_path;
+
+            if exe_path.is_empty() {
+                report.error(format!("{}: exe_path is not set", name));
+            } else if !exe_path.contains('{') && !Path::new(exe_path).exists() && !cmd.path.join(exe_path).exists() && !on_path(exe_path) {
+                report.error(format!("{}: executable '{}' not found", name, exe_path));
+            }
+        },
+        ConfigCommandSection::Cli(action) => {
+            if action.cli_cmd.is_empty() {
+                report.error(format!("{}: cli_cmd is not set", name));
+            }
+
+            if action.single_instance && action.wait {
+                report.warn(format!("{}: single_instance has no effect with wait", name));
+            }
+
+            if action.speak_output && !action.wait {
+                report.warn(format!("{}: speak_output has no effect without wait", name));
+            }
+        },
+        ConfigCommandSection::Http(action) if !action.url.starts_with("http://") && !action.url.starts_with("https://") => {
+            report.error(format!("{}: '{}' is not a valid http url", name, action.url));
+        },
+        ConfigCommandSection::Sequence(action) => {
+            if action.steps.is_empty() {
+                report.warn(format!("{}: sequence has no steps", name));
+            }
+
+            for (j, step) in action.steps.iter().enumerate() {
+                let step_name = format!("{} step #{}", name, j);
+
+                match step {
+                    SequenceStep::Sound { sound } => check_sound(report, &step_name, sound, voices),
+                    SequenceStep::Command { command } if super::find_by_id(command, commands).is_none() => {
+                        report.error(format!("{}: unknown command id '{}'", step_name, command));
+                    },
+                    SequenceStep::Action(action) => check_action(report, &step_name, cmd, action, commands, voices),
+                    _ => ()
+                }
+            }
+        },
+        _ => ()
+    }
+}
+
+fn check_sound(report: &mut Report, name: &str, sound: &str, voices: &[PathBuf]) {
+    let missing = voices
+        .iter()
+        .filter(|v| !v.join(format!("{}.wav", sound)).exists())
+        .map(|v| v.file_name().unwrap().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+
+    if !voices.is_empty() && missing.len() == voices.len() {
+        report.error(format!("{}: sound '{}' not found in any voice", name, sound));
+    } else if !missing.is_empty() {
+        report.warn(format!("{}: sound '{}' is missing in voice(s) {:?}", name, sound, missing));
+    }
+}
+
+// ids must be unique, sequences must not reference themselves
+fn check_ids(report: &mut Report, commands: &[AssistantCommand]) {
+    let mut seen: Vec<(&str, String)> = vec![];
+
+    for cmd in commands {
+        for (i, scmd) in cmd.commands.list.iter().enumerate() {
+            let id = scmd.id.as_str();
+
+            match seen.iter().find(|(other_id, _)| *other_id == id) {
+                Some((_, other)) => report.error(format!("{}: id '{}' is already used by {}", name(cmd, i), id, other)),
+                None => seen.push((id, name(cmd, i)))
+            }
+
+            if references(id, &scmd.command, commands, &mut vec![]) {
+                report.error(format!("{}: sequence '{}' references itself", name(cmd, i), id));
+            }
+        }
+    }
+}
+
+// whether the action (directly or via other commands) runs command `id`
+fn references<'a>(id: &str, action: &'a ConfigCommandSection, commands: &'a [AssistantCommand], visited: &mut Vec<&'a str>) -> bool {
+    let action = match action {
+        ConfigCommandSection::Sequence(action) => action,
+        _ => return false
+    };
+
+    action.steps.iter().any(|step| match step {
+        SequenceStep::Command { command } if command == id => true,
+        SequenceStep::Command { command } if !visited.contains(&command.as_str()) => {
+            visited.push(command);
+
+            match super::find_by_id(command, commands) {
+                Some((_, scmd)) => references(id, &scmd.command, commands, visited),
+                None => false
+            }
+        },
+        SequenceStep::Action(action) => references(id, action, commands, visited),
+        _ => false
+    })
+}
+
+fn check_duplicates(report: &mut Report, commands: &[AssistantCommand]) {
+    let mut seen: Vec<(String, String, *const Config)> = vec![];
+
+    for cmd in commands {
+        for (i, scmd) in cmd.commands.list.iter().enumerate() {
+            for phrase in &scmd.phrases {
+                let phrase = phrase.trim().to_lowercase();
+
+                match seen.iter().find(|(p, _, _)| *p == phrase) {
+                    Some((_, other, ptr)) if std::ptr::eq(*ptr, scmd) => {
+                        report.warn(format!("{}: phrase '{}' is listed twice", other, phrase));
+                    },
+                    Some((_, other, _)) => {
+                        report.error(format!("{}: phrase '{}' is already used by {}", name(cmd, i), phrase, other));
+                    },
+                    None => {
+                        seen.push((phrase, name(cmd, i), scmd as *const Config));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// phrases of one command, that would trigger another one
+fn check_collisions(report: &mut Report, commands: &[AssistantCommand]) {
+    for cmd in commands {
+        for (i, scmd) in cmd.commands.list.iter().enumerate() {
+            for other_cmd in commands {
+                for (j, other_scmd) in other_cmd.commands.list.iter().enumerate() {
+                    if std::ptr::eq(scmd, other_scmd) {
+                        continue
+                    }
+
+                    for phrase in other_scmd.phrases.iter().filter(|p| !slots::is_template(p)) {
+                        // exact duplicates are reported separately
+                        if scmd.phrases.iter().any(|p| p.trim().to_lowercase() == phrase.trim().to_lowercase()) {
+                            continue
+                        }
+
+                        if let Some((score, cmd_phrase, _)) = super::score_config(&phrase.to_lowercase(), scmd) {
+                            if score >= config::CMD_RATIO_THRESHOLD {
+                                report.warn(format!(
+                                    "{}: phrase '{}' also matches {} ('{}', score {:.1})",
+                                    name(other_cmd, j), phrase, name(cmd, i), cmd_phrase, score
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
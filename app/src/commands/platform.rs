@@ -0,0 +1,110 @@
+use super::{ConfigCommandSection, ExeAction, CliAction, SequenceStep};
+
+// name of the current os, as used in command files
+pub fn current() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        "linux"
+    }
+}
+
+// apply the current os variant of the command, returns false if it can't run here
+pub fn resolve(command: &mut ConfigCommandSection) -> bool {
+    match command {
+        // ahk scripts are compiled into windows executables
+        ConfigCommandSection::Ahk(action) => resolve_exe(action, cfg!(target_os = "windows")),
+        ConfigCommandSection::Exe(action) => resolve_exe(action, true),
+        ConfigCommandSection::Cli(action) => resolve_cli(action),
+        // every inline step must be able to run
+        ConfigCommandSection::Sequence(action) => action.steps.iter_mut().all(|step| match step {
+            SequenceStep::Action(action) => resolve(action),
+            _ => true
+        }),
+        _ => true
+    }
+}
+
+fn take_variant<T>(windows: &mut Option<T>, linux: &mut Option<T>, macos: &mut Option<T>) -> Option<T> {
+    match current() {
+        "windows" => windows.take(),
+        "macos" => macos.take(),
+        _ => linux.take(),
+    }
+}
+
+// `default_supported` - whether the top level exe_path runs on this os
+fn resolve_exe(action: &mut ExeAction, default_supported: bool) -> bool {
+    match take_variant(&mut action.windows, &mut action.linux, &mut action.macos) {
+        Some(variant) => {
+            if let Some(exe_path) = variant.exe_path {
+                action.exe_path = exe_path;
+            }
+            if let Some(exe_args) = variant.exe_args {
+                action.exe_args = exe_args;
+            }
+        },
+        None if !default_supported => return false,
+        None => ()
+    }
+
+    !action.exe_path.is_empty()
+}
+
+fn resolve_cli(action: &mut CliAction) -> bool {
+    if let Some(variant) = take_variant(&mut action.windows, &mut action.linux, &mut action.macos) {
+        if let Some(cli_cmd) = variant.cli_cmd {
+            action.cli_cmd = cli_cmd;
+        }
+        if let Some(cli_args) = variant.cli_args {
+            action.cli_args = cli_args;
+        }
+    }
+
+    !action.cli_cmd.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(yaml: &str) -> ConfigCommandSection {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn resolves_sequence_steps() {
+        let mut sequence = action(&format!("
+action: sequence
+steps:
+- delay: 100
+- action: exe
+  exe_path: default.exe
+  {}:
+    exe_path: variant.exe
+- action: cli
+  cli_cmd: default
+", current()));
+
+        assert!(resolve(&mut sequence));
+        match &sequence {
+            ConfigCommandSection::Sequence(action) => match &action.steps[1] {
+                SequenceStep::Action(ConfigCommandSection::Exe(action)) => assert_eq!(action.exe_path, "variant.exe"),
+                other => panic!("unexpected step {:?}", other)
+            },
+            other => panic!("unexpected action {:?}", other)
+        }
+
+        // ahk scripts only run on windows
+        let mut sequence = action("
+action: sequence
+steps:
+- action: ahk
+  exe_path: script.exe
+");
+
+        assert_eq!(resolve(&mut sequence), cfg!(target_os = "windows"));
+    }
+}
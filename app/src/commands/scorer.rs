@@ -0,0 +1,246 @@
+use seqdiff::ratio;
+
+use crate::{config, DB};
+use crate::config::structs::IntentRecognitionEngine;
+
+// common russian endings, longest first
+const ENDINGS: [&str; 49] = [
+    "ами", "ями", "ого", "его", "ому", "ему", "ыми", "ими", "ешь", "ете", "ить", "ать", "ять", "еть",
+    "ует", "ают", "яют", "ой", "ей", "ий", "ый", "ая", "яя", "ое", "ее", "ые", "ие", "ых", "их",
+    "ую", "юю", "ом", "ем", "ам", "ям", "ах", "ях", "ов", "ев", "а", "я", "о", "е", "ы", "и",
+    "у", "ю", "ь", "й",
+];
+const MIN_STEM_LENGTH: usize = 3;
+
+// prefixes turning a verb into its opposite ("включи" / "выключи", "отключи")
+const NEGATING_PREFIXES: [&str; 3] = ["вы", "за", "от"];
+
+// score phrase against command phrase, from 0 to 100
+pub fn score(phrase: &str, cmd_phrase: &str) -> f64 {
+    score_with(DB.get().unwrap().intent_recognition_engine, phrase, cmd_phrase)
+}
+
+pub fn score_with(engine: IntentRecognitionEngine, phrase: &str, cmd_phrase: &str) -> f64 {
+    match engine {
+        IntentRecognitionEngine::SeqDiff => seqdiff_score(phrase, cmd_phrase),
+        IntentRecognitionEngine::Token => token_score(phrase, cmd_phrase),
+    }
+}
+
+// the original character sequence ratio
+fn seqdiff_score(phrase: &str, cmd_phrase: &str) -> f64 {
+    ratio(&phrase.chars().collect::<Vec<_>>(), &cmd_phrase.chars().collect::<Vec<_>>())
+}
+
+fn token_score(phrase: &str, cmd_phrase: &str) -> f64 {
+    let phrase_tokens = tokenize(phrase);
+    let cmd_tokens = tokenize(cmd_phrase);
+
+    if phrase_tokens.is_empty() || cmd_tokens.is_empty() {
+        return 0.
+    }
+
+    // match every command token with the most similar (unused) phrase token
+    let mut used = vec![false; phrase_tokens.len()];
+    let mut matched = 0.;
+
+    for cmd_token in &cmd_tokens {
+        let mut best: Option<(usize, f64)> = None;
+
+        for (i, phrase_token) in phrase_tokens.iter().enumerate() {
+            if used[i] {
+                continue
+            }
+
+            let similarity = token_similarity(cmd_token, phrase_token);
+            if similarity >= config::CMD_TOKEN_MIN_SIMILARITY && best.is_none_or(|(_, s)| similarity > s) {
+                best = Some((i, similarity));
+            }
+        }
+
+        match best {
+            Some((i, similarity)) => {
+                used[i] = true;
+                matched += similarity;
+            },
+            // a different number means a different command ("громкость на 50" vs "громкость на 100")
+            None if is_number(cmd_token) => return 0.,
+            None => ()
+        }
+    }
+
+    // f1 of token recall (command words heard) and precision (extra words said)
+    let recall = matched / cmd_tokens.len() as f64;
+    let precision = matched / phrase_tokens.len() as f64;
+
+    if recall + precision == 0. {
+        0.
+    } else {
+        100. * 2. * recall * precision / (recall + precision)
+    }
+}
+
+// whether phrase contains given word (or some form of it)
+pub fn contains_word(phrase: &str, word: &str) -> bool {
+    let word = stem(&word.to_lowercase().replace('ё', "е"));
+
+    phrase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .any(|w| token_similarity(&stem(&w.to_lowercase().replace('ё', "е")), &word) >= config::CMD_TOKEN_MIN_SIMILARITY)
+}
+
+fn tokenize(phrase: &str) -> Vec<String> {
+    let words = phrase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase().replace('ё', "е"))
+        .collect::<Vec<_>>();
+
+    let tokens = words
+        .iter()
+        .filter(|w| !config::CMD_STOP_WORDS.contains(&w.as_str()))
+        .map(|w| stem(w))
+        .collect::<Vec<_>>();
+
+    // phrase consisting of stop words only
+    if tokens.is_empty() {
+        words.iter().map(|w| stem(w)).collect()
+    } else {
+        tokens
+    }
+}
+
+fn stem(word: &str) -> String {
+    let chars = word.chars().count();
+
+    for ending in ENDINGS {
+        if word.ends_with(ending) && chars - ending.chars().count() >= MIN_STEM_LENGTH {
+            return word[..word.len() - ending.len()].into()
+        }
+    }
+
+    word.into()
+}
+
+fn is_number(token: &str) -> bool {
+    token.parse::<f64>().is_ok()
+}
+
+fn token_similarity(a: &str, b: &str) -> f64 {
+    // numbers are never similar, only equal
+    if is_number(a) || is_number(b) {
+        return if a == b { 1. } else { 0. }
+    }
+
+    // a few letters apart, but the opposite meaning
+    if is_negation(a, b) {
+        return 0.
+    }
+
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let max_len = a.len().max(b.len());
+
+    if max_len == 0 {
+        return 1.
+    }
+
+    1. - levenshtein(&a, &b) as f64 / max_len as f64
+}
+
+// whether the words share a root, but only one of them has a negating prefix ("включ" / "выключ")
+fn is_negation(a: &str, b: &str) -> bool {
+    let root = a.chars().rev().zip(b.chars().rev()).take_while(|(x, y)| x == y).count();
+    if root < MIN_STEM_LENGTH {
+        return false
+    }
+
+    let a_prefix = a.chars().take(a.chars().count() - root).collect::<String>();
+    let b_prefix = b.chars().take(b.chars().count() - root).collect::<String>();
+
+    NEGATING_PREFIXES.contains(&a_prefix.as_str()) != NEGATING_PREFIXES.contains(&b_prefix.as_str())
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stems_words() {
+        let table = [
+            ("громкость", "громкост"),
+            ("браузеры", "браузер"),
+            ("браузер", "браузер"),
+            ("открой", "откр"),
+            ("музыку", "музык"),
+            ("сто", "сто"),
+            ("100", "100"),
+        ];
+
+        for (word, expected) in table {
+            assert_eq!(stem(word), expected, "word: {}", word);
+        }
+    }
+
+    #[test]
+    fn scores_tokens() {
+        let table = [
+            // same words, other forms, stop words & word order don't matter
+            ("открой браузер", "открой браузер", 100.),
+            ("открой браузер пожалуйста", "открой браузер", 100.),
+            ("браузер открой", "открой браузер", 100.),
+            ("открой браузеры", "открой браузер", 100.),
+            // f1 of recall and precision
+            ("открой браузер сейчас", "открой браузер", 80.),
+            ("открой", "открой браузер", 200. / 3.),
+            ("выключи свет", "открой браузер", 0.),
+            // negated verbs are different words
+            ("выключи калькулятор", "включи калькулятор", 50.),
+            ("отключи звук", "включи звук", 50.),
+            // numbers must be equal
+            ("поставь громкость на 100", "поставь громкость на 100", 100.),
+            ("поставь громкость на 100", "поставь громкость на 50", 0.),
+            ("поставь громкость на 10", "поставь громкость на 100", 0.),
+            ("поставь громкость на 100 процентов", "поставь громкость на 100", 600. / 7.),
+        ];
+
+        for (phrase, cmd_phrase, expected) in table {
+            let score = token_score(phrase, cmd_phrase);
+            assert!((score - expected).abs() < 0.1, "phrase: {}, command phrase: {}, score: {}", phrase, cmd_phrase, score);
+        }
+    }
+
+    #[test]
+    fn finds_words() {
+        let table = [
+            ("выключи звук", "звука", true),
+            ("выключи звук", "звук", true),
+            ("выключи свет", "звук", false),
+            ("громкость на 50", "50", true),
+            ("громкость на 500", "50", false),
+        ];
+
+        for (phrase, word, expected) in table {
+            assert_eq!(contains_word(phrase, word), expected, "phrase: {}, word: {}", phrase, word);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use seqdiff::ratio;
+
+use crate::{config, normalizer};
+use super::structs::ConfigSlotSection;
+
+// captured slot values, by slot name
+pub type Slots = HashMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotKind {
+    Text,   // one or more words
+    Word,   // exactly one word
+    Number, // exactly one numeric token
+}
+
+#[derive(Debug, PartialEq)]
+enum TemplatePart {
+    Word(String),
+    Slot { name: String, kind: SlotKind },
+}
+
+// phrase templates look like `поставь таймер на {minutes:number}`
+pub fn is_template(phrase: &str) -> bool {
+    phrase.split_whitespace().any(|w| parse_slot(w).is_some())
+}
+
+fn parse_slot(word: &str) -> Option<(String, SlotKind)> {
+    let inner = word.strip_prefix('{')?.strip_suffix('}')?;
+    let (name, kind) = match inner.split_once(':') {
+        Some((name, kind)) => (name, kind),
+        None => (inner, "text"),
+    };
+
+    if name.is_empty() {
+        return None
+    }
+
+    let kind = match kind {
+        "text" => SlotKind::Text,
+        "word" => SlotKind::Word,
+        "number" => SlotKind::Number,
+        _ => {
+            warn!("Unknown slot type '{}' in {}, treating it as text.", kind, word);
+            SlotKind::Text
+        }
+    };
+
+    Some((name.into(), kind))
+}
+
+fn parse_template(template: &str) -> Vec<TemplatePart> {
+    template.split_whitespace().map(|w| {
+        match parse_slot(w) {
+            Some((name, kind)) => TemplatePart::Slot { name, kind },
+            None => TemplatePart::Word(w.to_lowercase()),
+        }
+    }).collect()
+}
+
+fn word_ratio(a: &str, b: &str) -> f64 {
+    ratio(&a.chars().collect::<Vec<_>>(), &b.chars().collect::<Vec<_>>())
+}
+
+// returns (canonical spoken form, value to substitute)
+fn resolve_slot(spoken: &str, kind: SlotKind, slot_config: Option<&ConfigSlotSection>) -> Option<(String, String)> {
+    if kind == SlotKind::Number && spoken.parse::<f64>().is_err() {
+        return None
+    }
+
+    match slot_config {
+        Some(slot_config) if !slot_config.values.is_empty() => {
+            // enumerated slot, pick the closest known value
+            let mut best: Option<(&String, &String)> = None;
+            let mut best_ratio = config::CMD_SLOT_WORD_RATIO;
+
+            for (key, value) in &slot_config.values {
+                let r = word_ratio(&key.to_lowercase(), spoken);
+                if r >= best_ratio {
+                    best = Some((key, value));
+                    best_ratio = r;
+                }
+            }
+
+            best.map(|(key, value)| (key.to_lowercase(), value.clone()))
+        },
+        _ => Some((spoken.into(), spoken.into()))
+    }
+}
+
+fn match_parts(
+    parts: &[TemplatePart],
+    words: &[&str],
+    slot_configs: &HashMap<String, ConfigSlotSection>,
+    rendered: &mut Vec<String>,
+    slots: &mut Slots,
+) -> bool {
+    match parts.split_first() {
+        None => words.is_empty(),
+        Some((TemplatePart::Word(expected), rest)) => {
+            match words.split_first() {
+                Some((word, words_rest)) if word_ratio(expected, word) >= config::CMD_SLOT_WORD_RATIO => {
+                    rendered.push(expected.clone());
+                    if match_parts(rest, words_rest, slot_configs, rendered, slots) {
+                        return true
+                    }
+                    rendered.pop();
+
+                    false
+                },
+                _ => false
+            }
+        },
+        Some((TemplatePart::Slot { name, kind }, rest)) => {
+            let max_len = match kind {
+                SlotKind::Text => words.len(),
+                _ => words.len().min(1),
+            };
+
+            // prefer the shortest capture, so that trailing words can still match
+            for len in 1..=max_len {
+                let spoken = words[..len].join(" ");
+
+                if let Some((canonical, value)) = resolve_slot(&spoken, *kind, slot_configs.get(name)) {
+                    rendered.push(canonical);
+                    slots.insert(name.clone(), value);
+
+                    if match_parts(rest, &words[len..], slot_configs, rendered, slots) {
+                        return true
+                    }
+
+                    rendered.pop();
+                    slots.remove(name);
+                }
+            }
+
+            false
+        }
+    }
+}
+
+// match phrase against the template, returning the filled template and captured slots
+pub fn match_template(
+    template: &str,
+    phrase: &str,
+    slot_configs: &HashMap<String, ConfigSlotSection>,
+) -> Option<(String, Slots)> {
+    let parts = parse_template(template);
+    let words = phrase.split_whitespace().collect::<Vec<_>>();
+    let mut rendered: Vec<String> = vec![];
+    let mut slots = Slots::new();
+
+    if !match_parts(&parts, &words, slot_configs, &mut rendered, &mut slots) {
+        return None
+    }
+
+    // filled template is scored the same way as plain phrases
+    Some((rendered.join(" "), slots))
+}
+
+// words the template can be spoken with
+pub fn vocabulary(template: &str, slot_configs: &HashMap<String, ConfigSlotSection>) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+
+    for part in parse_template(template) {
+        match part {
+            // number words were normalized to digits, any of them could be spoken
+            TemplatePart::Word(word) if word.parse::<f64>().is_ok() => words.extend(normalizer::vocabulary()),
+            TemplatePart::Word(word) => words.push(word),
+            TemplatePart::Slot { name, kind } => {
+                match slot_configs.get(&name) {
+                    Some(slot_config) if !slot_config.values.is_empty() => {
+                        for key in slot_config.values.keys() {
+                            words.extend(key.to_lowercase().split_whitespace().map(String::from));
+                        }
+                    },
+                    _ if kind == SlotKind::Number => words.extend(normalizer::vocabulary()),
+                    _ => warn!("Slot {{{}}} in '{}' accepts free text, which can't be expressed with a grammar.", name, template)
+                }
+            }
+        }
+    }
+
+    words
+}
+
+// replace every `{name}` occurrence with the captured value
+pub fn substitute(input: &str, slots: &Slots) -> String {
+    substitute_with(input, slots, |value| value.into())
+}
+
+pub fn substitute_all(input: &[String], slots: &Slots) -> Vec<String> {
+    input.iter().map(|x| substitute(x, slots)).collect()
+}
+
+// same, but for a shell command line, values are quoted so that spoken text can't run anything
+pub fn substitute_shell(input: &str, slots: &Slots) -> String {
+    substitute_with(input, slots, shell_quote)
+}
+
+pub fn substitute_shell_args(input: &[String], slots: &Slots) -> Vec<String> {
+    if cfg!(target_os = "windows") {
+        // `cmd /C` joins arguments into its command line
+        input.iter().map(|x| substitute_with(x, slots, strip_cmd_chars)).collect()
+    } else {
+        // `sh -c` gets them as positional parameters, which aren't parsed
+        substitute_all(input, slots)
+    }
+}
+
+// single pass, so values are never substituted again
+fn substitute_with(input: &str, slots: &Slots, fill: fn(&str) -> String) -> String {
+    let mut out = String::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        match tail.find('}').and_then(|end| slots.get(&tail[1..end]).map(|value| (end, value))) {
+            Some((end, value)) => {
+                out.push_str(&fill(value));
+                rest = &tail[end + 1..];
+            },
+            None => {
+                out.push('{');
+                rest = &tail[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn shell_quote(value: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("\"{}\"", strip_cmd_chars(value))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+// cmd can't reliably escape these, even inside quotes
+fn strip_cmd_chars(value: &str) -> String {
+    value.chars().filter(|c| !"\"%!^&|<>()".contains(*c)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn slot_values(values: &[(&str, &str)]) -> HashMap<String, ConfigSlotSection> {
+        let values = values.iter().map(|(k, v)| (String::from(*k), String::from(*v))).collect();
+        HashMap::from([(String::from("site"), ConfigSlotSection { values })])
+    }
+
+    #[test]
+    fn parses_templates() {
+        assert!(is_template("поставь таймер на {minutes:number}"));
+        assert!(is_template("{site}"));
+        assert!(!is_template("открой браузер"));
+        assert!(!is_template("{} {:number}"));
+
+        assert_eq!(parse_template("Найди {query} в {site:word} {n:number}"), vec![
+            TemplatePart::Word("найди".into()),
+            TemplatePart::Slot { name: "query".into(), kind: SlotKind::Text },
+            TemplatePart::Word("в".into()),
+            TemplatePart::Slot { name: "site".into(), kind: SlotKind::Word },
+            TemplatePart::Slot { name: "n".into(), kind: SlotKind::Number },
+        ]);
+    }
+
+    #[test]
+    fn matches_templates() {
+        let none = HashMap::new();
+        let table = [
+            ("поставь таймер на {minutes:number}", "поставь таймер на 5", Some(("поставь таймер на 5", vec![("minutes", "5")]))),
+            ("поставь таймер на {minutes:number}", "поставь таймер на пять", None),
+            ("громкость {level:word}", "громкость на 50", None),
+            ("найди {query}", "найди рецепт пирога", Some(("найди рецепт пирога", vec![("query", "рецепт пирога")]))),
+            ("найди {query} в {place}", "найди рецепт в книге", Some(("найди рецепт в книге", vec![("query", "рецепт"), ("place", "книге")]))),
+            // fuzzy template words
+            ("открой {query}", "открои почту", Some(("открой почту", vec![("query", "почту")]))),
+            ("найди {query}", "найди", None),
+        ];
+
+        for (template, phrase, expected) in table {
+            let expected = expected.map(|(rendered, slots)| {
+                (String::from(rendered), slots.into_iter().map(|(k, v)| (String::from(k), String::from(v))).collect::<Slots>())
+            });
+            assert_eq!(match_template(template, phrase, &none), expected, "template: {}, phrase: {}", template, phrase);
+        }
+    }
+
+    #[test]
+    fn backtracks_text_slots() {
+        // the shortest capture ("рецепт") leaves "духовке в ютуб" for the site, which isn't a known value
+        let slot_configs = slot_values(&[("ютуб", "https://youtube.com"), ("гугл", "https://google.com")]);
+        let (rendered, slots) = match_template("найди {query} в {site}", "найди рецепт в духовке в ютуб", &slot_configs).unwrap();
+
+        assert_eq!(rendered, "найди рецепт в духовке в ютуб");
+        assert_eq!(slots["query"], "рецепт в духовке");
+        assert_eq!(slots["site"], "https://youtube.com");
+    }
+
+    #[test]
+    fn resolves_enumerated_slots() {
+        let slot_configs = slot_values(&[("ютуб", "https://youtube.com"), ("гугл", "https://google.com")]);
+
+        let (rendered, slots) = match_template("открой {site}", "открой гугол", &slot_configs).unwrap();
+        assert_eq!(rendered, "открой гугл");
+        assert_eq!(slots["site"], "https://google.com");
+
+        assert_eq!(match_template("открой {site}", "открой почту", &slot_configs), None);
+    }
+
+    #[test]
+    fn substitutes_slots() {
+        let slots = Slots::from([(String::from("a"), String::from("{b}")), (String::from("b"), String::from("x"))]);
+
+        assert_eq!(substitute("{a}-{b}-{c}-{", &slots), "{b}-x-{c}-{");
+        assert_eq!(substitute_all(&[String::from("{b}"), String::from("b")], &slots), vec!["x", "b"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quotes_shell_slots() {
+        let text = "it's $(echo injected); echo injected `echo injected` {b}";
+        let slots = Slots::from([(String::from("text"), String::from(text)), (String::from("b"), String::from("x"))]);
+
+        let cmd = substitute_shell("printf %s {text}", &slots);
+        let output = std::process::Command::new("sh").arg("-c").arg(&cmd).output().unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), text);
+        assert_eq!(substitute_shell_args(&[String::from("{text}")], &slots), vec![text]);
+    }
+}
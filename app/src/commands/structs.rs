@@ -1,5 +1,8 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use std::path::PathBuf;
+use std::collections::HashMap;
+
+use super::Slots;
 
 #[derive(Debug)]
 pub struct AssistantCommand {
@@ -7,6 +10,23 @@ pub struct AssistantCommand {
     pub commands: CommandsList,
 }
 
+#[derive(Debug)]
+pub struct Candidate<'a> {
+    pub path: &'a PathBuf,
+    pub config: &'a Config,
+    pub phrase: &'a str,
+    pub alternative: usize,
+    pub slots: Slots,
+    pub score: f64,
+}
+
+#[derive(Debug)]
+pub enum FetchResult<'a> {
+    Found(Candidate<'a>),
+    Ambiguous(Vec<Candidate<'a>>),
+    NotFound,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct CommandsList {
     pub list: Vec<Config>,
@@ -14,28 +34,212 @@ pub struct CommandsList {
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
+    // derived from the folder name and position, if not set
+    #[serde(default)]
+    pub id: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub version: Option<String>,
+
     pub command: ConfigCommandSection,
 
     pub voice: ConfigVoiceSection,
 
     pub phrases: Vec<String>,
+
+    #[serde(default)]
+    pub slots: HashMap<String, ConfigSlotSection>,
+
+    #[serde(default)]
+    pub exclude_phrases: Vec<String>,
+
+    #[serde(default)]
+    pub required_words: Vec<String>,
+
+    #[serde(default)]
+    pub min_ratio: Option<f64>,
+
+    // ask for a yes/no before running the command
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ConfigCommandSection {
+    Voice(VoiceAction),
+    Ahk(ExeAction),
+    Exe(ExeAction),
+    Cli(CliAction),
+    Http(HttpAction),
+    Terminate(TerminateAction),
+    StopChaining(StopChainingAction),
+    StopLast(StopLastAction),
+    Sequence(SequenceAction),
 }
 
 #[derive(Deserialize, Debug)]
-pub struct ConfigCommandSection {
-    pub action: String,
+pub struct VoiceAction {}
+
+// actions are tagged, so their fields are buffered by serde, which loses the lenient yaml handling
+// of empty values (`exe_args:`) and numbers in string lists (`- 25`), so it's done here
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Scalar {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+fn optional_string_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    let list: Option<Vec<Scalar>> = Option::deserialize(deserializer)?;
+
+    Ok(list.map(|list| list.into_iter().map(|x| match x {
+        Scalar::String(x) => x,
+        Scalar::Integer(x) => x.to_string(),
+        Scalar::Float(x) => x.to_string(),
+        Scalar::Bool(x) => x.to_string(),
+    }).collect()))
+}
 
+fn string_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(optional_string_list(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExeAction {
     #[serde(default)]
     pub exe_path: String,
 
-    #[serde(default)]
+    #[serde(default, deserialize_with = "string_list")]
     pub exe_args: Vec<String>,
 
+    // in seconds, the process is killed afterwards
+    pub timeout: Option<u64>,
+
+    // don't start the process again, while the previous one is running
+    #[serde(default)]
+    pub single_instance: bool,
+
+    // per-os overrides of exe_path and exe_args
+    pub windows: Option<ExeVariant>,
+    pub linux: Option<ExeVariant>,
+    pub macos: Option<ExeVariant>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExeVariant {
+    pub exe_path: Option<String>,
+    #[serde(default, deserialize_with = "optional_string_list")]
+    pub exe_args: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CliAction {
     #[serde(default)]
     pub cli_cmd: String,
 
+    #[serde(default, deserialize_with = "string_list")]
+    pub cli_args: Vec<String>,
+
+    // wait for the command to finish, its exit code decides which sounds are played
+    #[serde(default)]
+    pub wait: bool,
+
+    // in seconds, the process is killed afterwards
+    pub timeout: Option<u64>,
+
+    // say the output with tts (only used with `wait`)
+    #[serde(default)]
+    pub speak_output: bool,
+
+    // don't start the process again, while the previous one is running (ignored with `wait`)
+    #[serde(default)]
+    pub single_instance: bool,
+
+    // per-os overrides of cli_cmd and cli_args
+    pub windows: Option<CliVariant>,
+    pub linux: Option<CliVariant>,
+    pub macos: Option<CliVariant>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CliVariant {
+    pub cli_cmd: Option<String>,
+    #[serde(default, deserialize_with = "optional_string_list")]
+    pub cli_args: Option<Vec<String>>,
+}
+
+// captured result of a finished shell command
+#[derive(Debug)]
+pub struct CliOutput {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HttpAction {
+    #[serde(default)]
+    pub method: Option<String>,
+
+    pub url: String,
+
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+
+    #[serde(default)]
+    pub timeout: Option<u64>,
+
+    #[serde(default)]
+    pub expected_status: Option<u16>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TerminateAction {}
+
+#[derive(Deserialize, Debug)]
+pub struct StopChainingAction {}
+
+#[derive(Deserialize, Debug)]
+pub struct StopLastAction {}
+
+#[derive(Deserialize, Debug)]
+pub struct SequenceAction {
+    pub steps: Vec<SequenceStep>,
+
+    // run the remaining steps, even if some step fails
     #[serde(default)]
-    pub cli_args: Vec<String>
+    pub continue_on_failure: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum SequenceStep {
+    // pause, in milliseconds
+    Delay { delay: u64 },
+    // play the sound of the current voice
+    Sound { sound: String },
+    // run the action of another command by id
+    Command { command: String },
+    Action(ConfigCommandSection),
 }
 
 #[derive(Deserialize, Debug)]
@@ -43,4 +247,15 @@ pub struct ConfigVoiceSection {
 
     #[serde(default)]
     pub sounds: Vec<String>,
+
+    #[serde(default)]
+    pub failure_sounds: Vec<String>,
 }
+
+
+#[derive(Deserialize, Debug)]
+pub struct ConfigSlotSection {
+
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+}
\ No newline at end of file
@@ -0,0 +1,95 @@
+use std::process::Child;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use once_cell::sync::Lazy;
+
+// process spawned by some command
+struct Tracked {
+    key: String,
+    child: Child,
+    started: SystemTime,
+    timeout: Option<Duration>,
+}
+
+// most recently spawned last
+static PROCESSES: Lazy<Mutex<Vec<Tracked>>> = Lazy::new(|| Mutex::new(vec![]));
+
+// keep track of the spawned process, `key` identifies the command which spawned it
+pub fn track(key: &str, child: Child, timeout: Option<Duration>) {
+    info!("Tracking process {} spawned by {}.", child.id(), key);
+
+    PROCESSES.lock().unwrap().push(Tracked {
+        key: key.into(),
+        child,
+        started: SystemTime::now(),
+        timeout,
+    });
+}
+
+// whether the command still has some running process
+pub fn is_running(key: &str) -> bool {
+    reap();
+    PROCESSES.lock().unwrap().iter().any(|p| p.key == key)
+}
+
+// forget finished processes (so they don't become zombies) and kill the timed out ones
+pub fn reap() {
+    let mut processes = PROCESSES.lock().unwrap();
+
+    processes.retain_mut(|p| {
+        match p.child.try_wait() {
+            Ok(Some(status)) => {
+                info!("Process {} ({}) exited with {}.", p.child.id(), p.key, status);
+                false
+            },
+            Ok(None) => {
+                match (p.timeout, p.started.elapsed()) {
+                    (Some(timeout), Ok(elapsed)) if elapsed > timeout => {
+                        warn!("Process {} ({}) timed out after {:?}, killing it.", p.child.id(), p.key, timeout);
+                        kill(&mut p.child);
+                        false
+                    },
+                    _ => true
+                }
+            },
+            Err(msg) => {
+                error!("Can't get process {} ({}) status: {}", p.child.id(), p.key, msg);
+                false
+            }
+        }
+    });
+}
+
+// kill the most recently spawned process, that is still running
+pub fn stop_last() -> Result<(), String> {
+    reap();
+
+    match PROCESSES.lock().unwrap().pop() {
+        Some(mut p) => {
+            info!("Stopping process {} ({}).", p.child.id(), p.key);
+            kill(&mut p.child);
+            Ok(())
+        },
+        None => Err("No running command processes".into())
+    }
+}
+
+// kill the processes limited with a timeout (on exit)
+// the rest are apps the user opened (browser, steam, ...), they keep running
+pub fn stop_timed() {
+    for mut p in PROCESSES.lock().unwrap().drain(..) {
+        if p.timeout.is_some() {
+            info!("Stopping process {} ({}).", p.child.id(), p.key);
+            kill(&mut p.child);
+        }
+    }
+}
+
+fn kill(child: &mut Child) {
+    if let Err(msg) = child.kill() {
+        warn!("Can't kill process {}: {}", child.id(), msg);
+    }
+
+    // reap it
+    child.wait().ok();
+}
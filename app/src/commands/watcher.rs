@@ -0,0 +1,53 @@
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use once_cell::sync::Lazy;
+use notify::{Event, EventKind, RecursiveMode};
+
+use crate::{config, watcher};
+use super::AssistantCommand;
+
+// freshly parsed commands, waiting to be applied by the main loop
+static PENDING: Lazy<Mutex<Option<Vec<AssistantCommand>>>> = Lazy::new(|| Mutex::new(None));
+static HAS_PENDING: AtomicBool = AtomicBool::new(false);
+
+pub fn init() -> Result<(), ()> {
+    watcher::watch("Commands", Path::new(config::COMMANDS_PATH), RecursiveMode::Recursive, config::COMMANDS_RELOAD_DELAY, is_relevant, || {
+        info!("Commands directory changed, reloading commands ...");
+        match super::reparse_commands() {
+            Ok(commands) => {
+                *PENDING.lock().unwrap() = Some(commands);
+                HAS_PENDING.store(true, Ordering::SeqCst);
+            },
+            Err(msg) => {
+                warn!("Commands reload failed, keeping the current ones.\nError details: {}", msg);
+            }
+        }
+    })
+}
+
+fn is_relevant(event: &Event) -> bool {
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
+            // command files themselves or whole command folders
+            event.paths.iter().any(|p| {
+                p.is_dir() || p.file_name()
+                    .and_then(|f| f.to_str())
+                    .is_some_and(|f| config::COMMAND_FILE_NAMES.contains(&f))
+            })
+        },
+        _ => false
+    }
+}
+
+// apply reloaded commands, if any (should be called between utterances)
+pub fn apply_pending() {
+    if !HAS_PENDING.swap(false, Ordering::SeqCst) {
+        return
+    }
+
+    if let Some(commands) = PENDING.lock().unwrap().take() {
+        super::replace_commands(commands);
+    }
+}
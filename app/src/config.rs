@@ -1,13 +1,17 @@
 pub mod structs;
 use structs::WakeWordEngine;
 use structs::SpeechToTextEngine;
+use structs::SpeechToTextMode;
 use structs::RecorderType;
 use structs::AudioType;
+use structs::TextNormalizer;
+use structs::IntentRecognitionEngine;
+use structs::TextToSpeechEngine;
+use structs::VoiceActivityDetector;
 
 use std::fs;
 use std::env;
 use std::path::PathBuf;
-use once_cell::sync::Lazy;
 
 use platform_dirs::{AppDirs};
 use rustpotter::{RustpotterConfig, WavFmt, DetectorConfig, FiltersConfig, ScoreMode, GainNormalizationConfig, BandPassConfig};
@@ -59,11 +63,17 @@ pub const DEFAULT_AUDIO_TYPE: AudioType = AudioType::Kira;
 pub const DEFAULT_RECORDER_TYPE: RecorderType = RecorderType::PvRecorder;
 pub const DEFAULT_WAKE_WORD_ENGINE: WakeWordEngine = WakeWordEngine::Rustpotter;
 pub const DEFAULT_SPEECH_TO_TEXT_ENGINE: SpeechToTextEngine = SpeechToTextEngine::Vosk;
+pub const DEFAULT_SPEECH_TO_TEXT_MODE: SpeechToTextMode = SpeechToTextMode::FreeForm;
+pub const DEFAULT_TEXT_TO_SPEECH_ENGINE: TextToSpeechEngine = TextToSpeechEngine::EspeakNg;
+pub const DEFAULT_VOICE_ACTIVITY_DETECTOR: VoiceActivityDetector = VoiceActivityDetector::Energy;
+pub const DEFAULT_TEXT_NORMALIZER: TextNormalizer = TextNormalizer::Russian;
+pub const DEFAULT_INTENT_RECOGNITION_ENGINE: IntentRecognitionEngine = IntentRecognitionEngine::Token;
 
 pub const DEFAULT_VOICE: &str = "jarvis-og";
 
 pub const BUNDLE_IDENTIFIER: &str = "com.priler.jarvis";
 pub const DB_FILE_NAME: &str = "app.db";
+pub const SETTINGS_RELOAD_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
 pub const LOG_FILE_NAME: &str = "log.txt";
 pub const APP_VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 pub const AUTHOR_NAME: Option<&str> = option_env!("CARGO_PKG_AUTHORS");
@@ -79,12 +89,22 @@ pub const TRAY_TOOLTIP: &str = "Jarvis Voice Assistant";
 
 // RUSPOTTER
 pub const RUSPOTTER_MIN_SCORE: f32 = 0.62;
-pub const RUSTPOTTER_DEFAULT_CONFIG: Lazy<RustpotterConfig> = Lazy::new(|| {
+pub const RUSTPOTTER_DEFAULT_WAKE_WORDS: [&str; 5] = [
+    "rustpotter/jarvis-default.rpw",
+    "rustpotter/jarvis-community-1.rpw",
+    "rustpotter/jarvis-community-2.rpw",
+    "rustpotter/jarvis-community-3.rpw",
+    "rustpotter/jarvis-community-4.rpw",
+    // "rustpotter/jarvis-community-5.rpw",
+];
+
+// detector config with the given (raw) score threshold
+pub fn rustpotter_config(threshold: f32) -> RustpotterConfig {
     RustpotterConfig {
         fmt: WavFmt::default(),
         detector: DetectorConfig {
             avg_threshold: 0.,
-            threshold: 0.5,
+            threshold,
             min_scores: 15,
             score_mode: ScoreMode::Average,
             comparator_band_size: 5,
@@ -104,25 +124,85 @@ pub const RUSTPOTTER_DEFAULT_CONFIG: Lazy<RustpotterConfig> = Lazy::new(|| {
             }
         }
     }
-});
+}
+
+// wake-word enrollment
+pub const ENROLL_WAKE_WORDS_DIR: &str = "wakewords"; // inside config dir
+pub const ENROLL_DEFAULT_SAMPLES: usize = 4;
+pub const ENROLL_MIN_SAMPLES: usize = 3;
+pub const ENROLL_SAMPLE_DURATION: std::time::Duration = std::time::Duration::from_millis(2500);
+pub const ENROLL_SILENCE_RMS: f32 = 300.;
+
+// wake-word calibration
+pub const CALIBRATE_DETECTOR_THRESHOLD: f32 = 0.3; // lower than usual, to see the whole score distribution
+pub const CALIBRATE_MAX_FALSE_ALARMS_PER_HOUR: f64 = 1.;
+pub const CALIBRATE_TRAILING_SILENCE: std::time::Duration = std::time::Duration::from_secs(1);
 
 // PICOVOICE
 pub const COMMANDS_PATH: &str = "commands/";
+pub const COMMANDS_RELOAD_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+pub const COMMAND_FILE_NAMES: [&str; 3] = ["command.yaml", "command.json", "command.toml"]; // in order of precedence
 pub const KEYWORDS_PATH: &str = "picovoice/keywords/";
 pub const DEFAULT_KEYWORD: &str = "jarvis_windows.ppn";
 pub const DEFAULT_SENSITIVITY: f32 = 1.0;
 
+// RECORDER
+pub const RECORDER_SAMPLE_RATE: u32 = 16000;
+
 // VOSK
 // pub const VOSK_MODEL_PATH: &str = const_concat!(PUBLIC_PATH, "/vosk/model_small");
 pub const VOSK_FETCH_PHRASE: &str = "джарвис";
 pub const VOSK_MODEL_PATH: &str = "vosk/model_small";
 pub const VOSK_MIN_RATIO: f64 = 70.0;
+pub const VOSK_UNKNOWN_WORD: &str = "[unk]";
+pub const VOSK_MAX_CONFIDENCE_GAP: f32 = 10.; // alternatives further behind the best one are ignored
+
+// VAD
+pub const VAD_MIN_ENERGY: f32 = 200.;
+pub const VAD_ENERGY_RATIO: f32 = 3.; // speech is that much louder than the noise floor
+pub const VAD_MAX_ZERO_CROSSING_RATE: f32 = 0.45;
+pub const VAD_HANGOVER_FRAMES: usize = 10; // ~320ms of 512 samples frames
+pub const VAD_PRE_SPEECH_FRAMES: usize = 8; // ~250ms of 512 samples frames, fed to stt when the speech starts
+pub const VAD_NOISE_ADAPT_UP: f32 = 0.01;
+pub const VAD_NOISE_ADAPT_DOWN: f32 = 0.1;
+pub const VAD_NO_SPEECH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(6);
+pub const VAD_END_OF_SPEECH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+// TTS
+pub const TTS_OUTPUT_FILE_NAME: &str = "jarvis-tts.wav";
+pub const ESPEAK_NG_BIN: &str = "espeak-ng";
+pub const ESPEAK_NG_DEFAULT_VOICE: &str = "ru";
+pub const RHVOICE_BIN: &str = "RHVoice-test";
+pub const RHVOICE_DEFAULT_VOICE: &str = "aleksandr";
 
 // ETC
 pub const CMD_RATIO_THRESHOLD: f64 = 65f64;
+pub const CMD_SLOT_WORD_RATIO: f64 = 75f64;
+pub const CMD_TOKEN_MIN_SIMILARITY: f64 = 0.7;
+pub const CMD_DEBUG_CANDIDATES: usize = 5;
+pub const DEFAULT_CMD_AMBIGUITY_MARGIN: f64 = 5f64;
+pub const CMD_AMBIGUITY_MAX_OPTIONS: usize = 3;
+pub const CMD_CLARIFY_WAIT_DELAY: std::time::Duration = std::time::Duration::from_secs(7);
+pub const CMD_CONFIRM_WAIT_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+pub const CLI_DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+pub const CLI_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+pub const SEQUENCE_MAX_DEPTH: usize = 5;
+pub const HTTP_DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 pub const CMS_WAIT_DELAY: std::time::Duration = std::time::Duration::from_secs(15);
 
 pub const ASSISTANT_GREET_PHRASES: [&str; 3] = ["greet1", "greet2", "greet3"];
+pub const ASSISTANT_CLARIFY_PHRASE: &str = "clarify";
+// answers to the clarifying question, up to CMD_AMBIGUITY_MAX_OPTIONS (normalized to digits)
+pub const ASSISTANT_CLARIFY_WORDS: [&str; 15] = [
+    "первый", "первая", "первое", "первую", "один",
+    "второй", "вторая", "второе", "вторую", "два",
+    "третий", "третья", "третье", "третью", "три",
+];
+pub const ASSISTANT_CONFIRM_PHRASE: &str = "confirm";
+
+// default replies to the confirmation prompt (words from ASSISTANT_PHRASES_TBR won't work)
+pub const DEFAULT_CONFIRM_WORDS: [&str; 6] = ["да", "подтверждаю", "конечно", "выполняй", "верно", "точно"];
+pub const DEFAULT_DENY_WORDS: [&str; 5] = ["нет", "отмена", "не надо", "стоп", "не нужно"];
 pub const ASSISTANT_PHRASES_TBR: [&str; 17] = [
     "джарвис",
     "сэр",
@@ -142,3 +222,34 @@ pub const ASSISTANT_PHRASES_TBR: [&str; 17] = [
     "загружаю сэр",
     "очень тонкое замечание сэр",
 ];
+
+
+// words ignored by the token scorer
+pub const CMD_STOP_WORDS: [&str; 26] = [
+    "а",
+    "и",
+    "в",
+    "во",
+    "на",
+    "с",
+    "со",
+    "к",
+    "ко",
+    "у",
+    "о",
+    "об",
+    "по",
+    "за",
+    "же",
+    "ну",
+    "ка",
+    "бы",
+    "ли",
+    "то",
+    "вот",
+    "мне",
+    "меня",
+    "пожалуйста",
+    "это",
+    "можешь",
+];
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::config;
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 pub enum WakeWordEngine {
@@ -12,6 +13,23 @@ pub enum SpeechToTextEngine {
     Vosk
 }
 
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum SpeechToTextMode {
+    FreeForm,
+    Grammar
+}
+
+impl Default for SpeechToTextMode {
+    fn default() -> SpeechToTextMode {
+        config::DEFAULT_SPEECH_TO_TEXT_MODE
+    }
+}
+
+#[derive(Debug)]
+pub enum TextNormalizer {
+    Russian
+}
+
 #[derive(PartialEq, Debug)]
 pub enum RecorderType {
     Cpal,
@@ -25,6 +43,38 @@ pub enum AudioType {
     Kira
 }
 
-// pub enum TextToSpeechEngine {}
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum TextToSpeechEngine {
+    EspeakNg,
+    RhVoice
+}
+
+impl Default for TextToSpeechEngine {
+    fn default() -> TextToSpeechEngine {
+        config::DEFAULT_TEXT_TO_SPEECH_ENGINE
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum VoiceActivityDetector {
+    Energy,
+    Disabled
+}
+
+impl Default for VoiceActivityDetector {
+    fn default() -> VoiceActivityDetector {
+        config::DEFAULT_VOICE_ACTIVITY_DETECTOR
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum IntentRecognitionEngine {
+    SeqDiff,
+    Token
+}
 
-// pub enum IntentRecognitionEngine {}
\ No newline at end of file
+impl Default for IntentRecognitionEngine {
+    fn default() -> IntentRecognitionEngine {
+        config::DEFAULT_INTENT_RECOGNITION_ENGINE
+    }
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
 pub mod structs;
+pub mod watcher;
 use crate::{config, APP_CONFIG_DIR};
 
 use std::path::PathBuf;
@@ -8,7 +9,7 @@ use log::info;
 
 use serde_json;
 
-fn get_db_file_path() -> PathBuf {
+pub fn get_db_file_path() -> PathBuf {
     PathBuf::from(format!("{}/{}", APP_CONFIG_DIR.get().unwrap().display(), config::DB_FILE_NAME))
 }
 
@@ -20,12 +21,9 @@ pub fn init_settings() -> structs::Settings {
 
     if db_file_path.exists() {
         // try load existing settings
-        if let Ok(mut db_file) = File::open(db_file_path) {
-            let reader = BufReader::new(db_file);
-            if let Ok(parsed_json) = serde_json::from_reader(reader) {
-                info!("Settings loaded.");
-                db = Some(parsed_json);
-            }
+        db = read_settings(&db_file_path);
+        if db.is_some() {
+            info!("Settings loaded.");
         }
     }
 
@@ -38,6 +36,13 @@ pub fn init_settings() -> structs::Settings {
     db.unwrap()
 }
 
+pub fn read_settings(db_file_path: &PathBuf) -> Option<structs::Settings> {
+    let db_file = File::open(db_file_path).ok()?;
+    let reader = BufReader::new(db_file);
+
+    serde_json::from_reader(reader).ok()
+}
+
 pub fn save_settings(settings: &structs::Settings) -> Result<(), std::io::Error> {
     let db_file_path = get_db_file_path();
 
@@ -1,8 +1,13 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use crate::config;
 
 use crate::config::structs::WakeWordEngine;
 use crate::config::structs::SpeechToTextEngine;
+use crate::config::structs::SpeechToTextMode;
+use crate::config::structs::IntentRecognitionEngine;
+use crate::config::structs::TextToSpeechEngine;
+use crate::config::structs::VoiceActivityDetector;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Settings {
@@ -12,6 +17,40 @@ pub struct Settings {
     pub wake_word_engine: WakeWordEngine,
     pub speech_to_text_engine: SpeechToTextEngine,
 
+    #[serde(default)]
+    pub rustpotter: RustpotterSettings,
+
+    // by wake-word name, wake-words not listed here behave the default way
+    #[serde(default)]
+    pub wake_word_behaviours: HashMap<String, WakeWordBehaviour>,
+
+    #[serde(default)]
+    pub speech_to_text_mode: SpeechToTextMode,
+
+    #[serde(default)]
+    pub voice_activity_detector: VoiceActivityDetector,
+
+    #[serde(default)]
+    pub intent_recognition_engine: IntentRecognitionEngine,
+
+    // commands scoring this close to the best one are ambiguous, and the user is asked to pick one
+    #[serde(default = "ambiguity_margin_default")]
+    pub ambiguity_margin: f64,
+
+    #[serde(default)]
+    pub text_to_speech_engine: TextToSpeechEngine,
+
+    // empty means the engine default
+    #[serde(default)]
+    pub text_to_speech_voice: String,
+
+    #[serde(default)]
+    pub confirmation: ConfirmationWords,
+
+    // ids of the commands, that shouldn't be recognized
+    #[serde(default)]
+    pub disabled_commands: Vec<String>,
+
     pub api_keys: ApiKeys
 }
 
@@ -23,6 +62,16 @@ impl Default for Settings {
 
             wake_word_engine: config::DEFAULT_WAKE_WORD_ENGINE,
             speech_to_text_engine: config::DEFAULT_SPEECH_TO_TEXT_ENGINE,
+            rustpotter: RustpotterSettings::default(),
+            wake_word_behaviours: HashMap::new(),
+            speech_to_text_mode: config::DEFAULT_SPEECH_TO_TEXT_MODE,
+            voice_activity_detector: config::DEFAULT_VOICE_ACTIVITY_DETECTOR,
+            intent_recognition_engine: config::DEFAULT_INTENT_RECOGNITION_ENGINE,
+            ambiguity_margin: config::DEFAULT_CMD_AMBIGUITY_MARGIN,
+            text_to_speech_engine: config::DEFAULT_TEXT_TO_SPEECH_ENGINE,
+            text_to_speech_voice: String::from(""),
+            confirmation: ConfirmationWords::default(),
+            disabled_commands: vec![],
 
             api_keys: ApiKeys {
                 picovoice: String::from(""),
@@ -32,6 +81,74 @@ impl Default for Settings {
     }
 }
 
+fn ambiguity_margin_default() -> f64 {
+    config::DEFAULT_CMD_AMBIGUITY_MARGIN
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RustpotterSettings {
+    pub wake_words: Vec<WakeWordFile>,
+
+    // minimal detection score
+    pub threshold: f32,
+}
+
+impl Default for RustpotterSettings {
+    fn default() -> RustpotterSettings {
+        RustpotterSettings {
+            wake_words: config::RUSTPOTTER_DEFAULT_WAKE_WORDS.iter().map(|x| WakeWordFile {
+                path: String::from(*x),
+                enabled: true,
+            }).collect(),
+            threshold: config::RUSPOTTER_MIN_SCORE,
+        }
+    }
+}
+
+// .rpw file, relative to the app directory or absolute
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WakeWordFile {
+    pub path: String,
+
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct WakeWordBehaviour {
+    // greet sounds of the current voice, instead of the default ones
+    #[serde(default)]
+    pub greet_sounds: Vec<String>,
+
+    // ids or tags of the commands to listen for, all commands if empty
+    #[serde(default)]
+    pub commands: Vec<String>,
+
+    // id of the command to run right away, without listening
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+// replies to the confirmation prompt of dangerous commands
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConfirmationWords {
+    pub confirm: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl Default for ConfirmationWords {
+    fn default() -> ConfirmationWords {
+        ConfirmationWords {
+            confirm: config::DEFAULT_CONFIRM_WORDS.iter().map(|x| String::from(*x)).collect(),
+            deny: config::DEFAULT_DENY_WORDS.iter().map(|x| String::from(*x)).collect(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApiKeys {
     pub picovoice: String,
@@ -0,0 +1,40 @@
+use notify::{Event, EventKind, RecursiveMode};
+
+use crate::{config, listener, watcher, DB, APP_CONFIG_DIR};
+
+// settings are loaded once, so only wake-word files & threshold are applied on change,
+// everything else still needs a restart
+pub fn init() -> Result<(), ()> {
+    let mut current = DB.get().unwrap().rustpotter.clone();
+
+    // watch the whole config dir, since the settings file may be replaced rather than written in place
+    watcher::watch("Settings", APP_CONFIG_DIR.get().unwrap(), RecursiveMode::NonRecursive, config::SETTINGS_RELOAD_DELAY, is_relevant, move || {
+        let settings = match super::read_settings(&super::get_db_file_path()) {
+            Some(settings) => settings,
+            None => {
+                warn!("Settings file changed, but cannot be parsed, keeping the current settings.");
+                return
+            }
+        };
+
+        if settings.rustpotter == current {
+            return
+        }
+
+        info!("Wake-word settings changed, applying them ...");
+        if listener::reload_rustpotter(&settings.rustpotter).is_ok() {
+            current = settings.rustpotter;
+        } else {
+            warn!("Wake-word settings reload failed, keeping the current ones.");
+        }
+    })
+}
+
+fn is_relevant(event: &Event) -> bool {
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(_) => {
+            event.paths.iter().any(|p| p.file_name().and_then(|f| f.to_str()) == Some(config::DB_FILE_NAME))
+        },
+        _ => false
+    }
+}
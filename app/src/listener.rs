@@ -1,6 +1,8 @@
 mod porcupine;
 
 mod rustpotter;
+pub mod enrollment;
+pub mod calibration;
 
 mod vosk;
 
@@ -11,6 +13,7 @@ use crate::{config, stt};
 use crate::config::structs::WakeWordEngine;
 
 use crate::DB;
+use crate::db::structs::{RustpotterSettings, WakeWordBehaviour};
 
 // store wake-word engine being used
 static WAKE_WORD_ENGINE: OnceCell<WakeWordEngine> = OnceCell::new();
@@ -48,7 +51,8 @@ pub fn init() -> Result<(), ()> {
     }
 }
 
-pub fn data_callback(frame_buffer: &[i16]) -> Option<i32> {
+// returns name of the detected wake-word
+pub fn data_callback(frame_buffer: &[i16]) -> Option<String> {
     match WAKE_WORD_ENGINE.get().unwrap() {
         WakeWordEngine::Porcupine => {
             porcupine::data_callback(frame_buffer)
@@ -60,4 +64,24 @@ pub fn data_callback(frame_buffer: &[i16]) -> Option<i32> {
             vosk::data_callback(frame_buffer)
         }
     }
-}
\ No newline at end of file
+}
+
+// apply new wake-word files & threshold without restarting
+pub fn reload_rustpotter(settings: &RustpotterSettings) -> Result<(), ()> {
+    match WAKE_WORD_ENGINE.get() {
+        None => Ok(()), // not listening yet, settings are used on init
+        Some(WakeWordEngine::Rustpotter) => {
+            info!("Reloading Rustpotter wake-word engine.");
+            rustpotter::reload(settings)
+        },
+        Some(engine) => {
+            warn!("Current wake-word engine is {:?}, Rustpotter settings are not used.", engine);
+            Err(())
+        }
+    }
+}
+
+// what to do, once the given wake-word is detected
+pub fn behaviour(wake_word: &str) -> Option<&'static WakeWordBehaviour> {
+    DB.get().unwrap().wake_word_behaviours.get(wake_word)
+}
@@ -0,0 +1,297 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustpotter::Rustpotter;
+
+use crate::{config, DB};
+use crate::config::structs::WakeWordEngine;
+
+struct Sample {
+    path: PathBuf,
+    data: Vec<i16>,
+    seconds: f64,
+}
+
+// detection scores of a single wake-word file
+#[derive(Default)]
+struct Scores {
+    // best score in every positive sample (None if not detected at all)
+    positives: Vec<Option<f32>>,
+    // every detection in negative samples
+    negatives: Vec<f32>,
+}
+
+impl Scores {
+    fn detection_rate(&self, threshold: f32) -> f64 {
+        if self.positives.is_empty() {
+            return 0.
+        }
+
+        let detected = self.positives.iter().filter(|s| s.is_some_and(|s| s > threshold)).count();
+        detected as f64 / self.positives.len() as f64
+    }
+
+    fn false_alarms(&self, threshold: f32) -> usize {
+        self.negatives.iter().filter(|s| **s > threshold).count()
+    }
+}
+
+// run wake-word files from settings over labelled samples and print statistics, returns exit code
+// samples are 16 kHz wav files in `positive` (wake-word said) and `negative` (anything else) subdirectories
+pub fn run(dir: &Path) -> i32 {
+    let settings = DB.get().unwrap();
+
+    match settings.wake_word_engine {
+        WakeWordEngine::Rustpotter => (),
+        engine => {
+            println!("Calibration is only supported for Rustpotter, current wake-word engine is {:?}.", engine);
+            return 1
+        }
+    }
+
+    let positives = load_samples(&dir.join("positive"));
+    let negatives = load_samples(&dir.join("negative"));
+    if positives.is_empty() && negatives.is_empty() {
+        println!("No samples found, put wav files into {} and {}.", dir.join("positive").display(), dir.join("negative").display());
+        return 1
+    }
+
+    let negative_hours = negatives.iter().map(|s| s.seconds).sum::<f64>() / 3600.;
+    let threshold = settings.rustpotter.threshold;
+
+    println!("Positive samples: {}", positives.len());
+    println!("Negative samples: {} ({:.2} h)", negatives.len(), negative_hours);
+    println!("Current threshold: {}", threshold);
+
+    let mut failed = false;
+    for wake_word in settings.rustpotter.wake_words.iter().filter(|w| w.enabled) {
+        println!("\n== {} ==", wake_word.path);
+
+        match score_file(&wake_word.path, &positives, &negatives) {
+            Ok(scores) => report(&scores, threshold, negative_hours),
+            Err(msg) => {
+                println!("[ERROR] Cannot load wake-word file: {}", msg);
+                failed = true;
+            }
+        }
+    }
+
+    println!("\nNote: all enabled files are used together, so their false alarms add up.");
+
+    if failed { 1 } else { 0 }
+}
+
+fn load_samples(dir: &Path) -> Vec<Sample> {
+    let mut files = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|x| x.eq_ignore_ascii_case("wav")))
+            .collect::<Vec<_>>(),
+        Err(_) => vec![]
+    };
+    files.sort();
+
+    files.into_iter().filter_map(|path| {
+        match read_wav(&path) {
+            Ok(data) => {
+                let seconds = data.len() as f64 / config::RECORDER_SAMPLE_RATE as f64;
+                Some(Sample { path, data, seconds })
+            },
+            Err(msg) => {
+                println!("[WARN]  Skipping {}: {}", path.display(), msg);
+                None
+            }
+        }
+    }).collect()
+}
+
+// mono 16-bit samples
+fn read_wav(path: &Path) -> Result<Vec<i16>, String> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| e.to_string())?;
+    let spec = reader.spec();
+
+    if spec.sample_rate != config::RECORDER_SAMPLE_RATE {
+        return Err(format!("sample rate is {} Hz, {} Hz expected", spec.sample_rate, config::RECORDER_SAMPLE_RATE))
+    }
+
+    let samples: Vec<i16> = match spec.sample_format {
+        hound::SampleFormat::Int => reader
+            .samples::<i32>()
+            .map(|x| x.map(|x| match spec.bits_per_sample {
+                bits if bits > 16 => (x >> (bits - 16)) as i16,
+                bits => (x << (16 - bits)) as i16
+            }))
+            .collect::<Result<_, _>>(),
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|x| x.map(|x| (x.clamp(-1., 1.) * i16::MAX as f32) as i16))
+            .collect::<Result<_, _>>(),
+    }.map_err(|e| e.to_string())?;
+
+    // downmix
+    let channels = spec.channels.max(1) as usize;
+    Ok(samples
+        .chunks(channels)
+        .map(|frame| (frame.iter().map(|x| *x as i32).sum::<i32>() / frame.len() as i32) as i16)
+        .collect())
+}
+
+fn score_file(rpw: &str, positives: &[Sample], negatives: &[Sample]) -> Result<Scores, String> {
+    let mut scores = Scores::default();
+
+    for sample in positives {
+        let detections = detect(rpw, sample)?;
+        scores.positives.push(detections.into_iter().reduce(f32::max));
+    }
+
+    for sample in negatives {
+        scores.negatives.extend(detect(rpw, sample)?);
+    }
+
+    Ok(scores)
+}
+
+// scores of every detection in the sample, with a fresh detector
+fn detect(rpw: &str, sample: &Sample) -> Result<Vec<f32>, String> {
+    let mut rustpotter = Rustpotter::new(&config::rustpotter_config(config::CALIBRATE_DETECTOR_THRESHOLD))?;
+    rustpotter.add_wakeword_from_file(rpw)?;
+
+    // trailing silence lets the detection at the very end come out
+    let silence = (config::CALIBRATE_TRAILING_SILENCE.as_millis() as usize) * (config::RECORDER_SAMPLE_RATE as usize) / 1000;
+    let mut data = sample.data.clone();
+    data.extend(std::iter::repeat_n(0, silence));
+
+    let mut out: Vec<f32> = vec![];
+    for frame in data.chunks_exact(rustpotter.get_samples_per_frame()) {
+        if let Some(detection) = rustpotter.process_i16(frame) {
+            debug!("Detection in {}: {:?}", sample.path.display(), detection);
+            out.push(detection.score);
+        }
+    }
+
+    Ok(out)
+}
+
+fn report(scores: &Scores, threshold: f32, negative_hours: f64) {
+    if !scores.positives.is_empty() {
+        let detected = (scores.detection_rate(threshold) * scores.positives.len() as f64).round();
+        println!("Detection rate: {:.1}% ({} of {})", scores.detection_rate(threshold) * 100., detected, scores.positives.len());
+
+        let positive_scores = scores.positives.iter().filter_map(|x| *x).collect::<Vec<_>>();
+        println!("Positive scores: {}", distribution(&positive_scores));
+
+        let missed = scores.positives.iter().filter(|x| x.is_none()).count();
+        if missed > 0 {
+            println!("Not detected at all (score below {}): {}", config::CALIBRATE_DETECTOR_THRESHOLD, missed);
+        }
+    }
+
+    if negative_hours > 0. {
+        let false_alarms = scores.false_alarms(threshold);
+        println!("False alarms: {} ({:.2} per hour)", false_alarms, false_alarms as f64 / negative_hours);
+        println!("False alarm scores: {}", distribution(&scores.negatives));
+    }
+
+    match suggest_threshold(scores, negative_hours) {
+        Some(suggested) => {
+            println!(
+                "Suggested threshold: {:.2} (detection rate {:.1}%, {:.2} false alarms per hour)",
+                suggested,
+                scores.detection_rate(suggested) * 100.,
+                scores.false_alarms(suggested) as f64 / negative_hours
+            );
+            println!("Set it as rustpotter.threshold in the settings, the detector uses it as is.");
+        },
+        None if negative_hours == 0. => println!("Add negative samples to get a threshold suggestion."),
+        None => println!("No threshold keeps false alarms under {} per hour, the wake-word file should be re-recorded.", config::CALIBRATE_MAX_FALSE_ALARMS_PER_HOUR)
+    }
+}
+
+// the lowest threshold (so the best detection rate), that keeps false alarms acceptable
+fn suggest_threshold(scores: &Scores, negative_hours: f64) -> Option<f32> {
+    if negative_hours == 0. {
+        return None
+    }
+
+    let start = (config::CALIBRATE_DETECTOR_THRESHOLD * 100.) as u32;
+    (start..100)
+        .map(|x| x as f32 / 100.)
+        .find(|t| scores.false_alarms(*t) as f64 / negative_hours <= config::CALIBRATE_MAX_FALSE_ALARMS_PER_HOUR)
+}
+
+fn distribution(scores: &[f32]) -> String {
+    if scores.is_empty() {
+        return String::from("none")
+    }
+
+    let mut sorted = scores.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+
+    format!(
+        "min {:.2}, p10 {:.2}, median {:.2}, p90 {:.2}, max {:.2}",
+        sorted[0], percentile(0.1), percentile(0.5), percentile(0.9), sorted[sorted.len() - 1]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores() -> Scores {
+        Scores {
+            positives: vec![Some(0.9), Some(0.6), None, Some(0.45)],
+            negatives: vec![0.35, 0.5, 0.55],
+        }
+    }
+
+    #[test]
+    fn counts_detections() {
+        let table = [
+            (0.2, 0.75, 3),
+            (0.4, 0.75, 2),
+            (0.5, 0.5, 1),
+            (0.55, 0.5, 0),
+            (0.95, 0., 0),
+        ];
+
+        for (threshold, detection_rate, false_alarms) in table {
+            assert_eq!(scores().detection_rate(threshold), detection_rate, "threshold: {}", threshold);
+            assert_eq!(scores().false_alarms(threshold), false_alarms, "threshold: {}", threshold);
+        }
+
+        assert_eq!(Scores::default().detection_rate(0.5), 0.);
+        assert_eq!(Scores::default().false_alarms(0.5), 0);
+    }
+
+    #[test]
+    fn suggests_thresholds() {
+        let noisy = Scores { positives: vec![Some(0.99)], negatives: vec![0.995, 0.995] };
+
+        let table = [
+            // the lowest one, with at most CALIBRATE_MAX_FALSE_ALARMS_PER_HOUR
+            (scores(), 1., Some(0.5)),
+            (scores(), 2., Some(0.35)),
+            (scores(), 0., None),
+            (noisy, 1., None),
+        ];
+
+        for (scores, negative_hours, expected) in table {
+            assert_eq!(suggest_threshold(&scores, negative_hours), expected, "negative hours: {}", negative_hours);
+        }
+    }
+
+    #[test]
+    fn describes_distribution() {
+        let table = [
+            (vec![], "none"),
+            (vec![0.5], "min 0.50, p10 0.50, median 0.50, p90 0.50, max 0.50"),
+            (vec![0.3, 0.1, 1.0, 0.2, 0.5, 0.4, 0.7, 0.6, 0.9, 0.8], "min 0.10, p10 0.20, median 0.60, p90 0.90, max 1.00"),
+        ];
+
+        for (scores, expected) in table {
+            assert_eq!(distribution(&scores), expected, "scores: {:?}", scores);
+        }
+    }
+}
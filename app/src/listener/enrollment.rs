@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rustpotter::Wakeword;
+
+use crate::{config, db, recorder, APP_CONFIG_DIR};
+use crate::db::structs::WakeWordFile;
+
+// record samples of the wake phrase, build .rpw file out of them and register it in settings
+// recorder must be initialized
+pub fn run(name: &str, samples: usize) -> Result<PathBuf, String> {
+    if name.trim().is_empty() {
+        return Err("Wake phrase is empty".into())
+    }
+
+    if samples < config::ENROLL_MIN_SAMPLES {
+        return Err(format!("At least {} samples are required", config::ENROLL_MIN_SAMPLES))
+    }
+
+    let file_name = file_name(name);
+    let dir = APP_CONFIG_DIR.get().unwrap().join(config::ENROLL_WAKE_WORDS_DIR);
+    let samples_dir = dir.join(&file_name);
+    fs::create_dir_all(&samples_dir).map_err(|msg| format!("Cannot create {} ({})", samples_dir.display(), msg))?;
+
+    // record
+    recorder::start_recording().map_err(|_| String::from("Cannot start recording"))?;
+    let recorded = record_samples(name, samples, &samples_dir);
+    recorder::stop_recording().ok();
+    let sample_files = recorded?;
+
+    // build
+    println!("Building wake-word ...");
+    let rpw = dir.join(format!("{}.rpw", file_name));
+    let wakeword = Wakeword::new_from_sample_files(
+        name.into(),
+        None,
+        None,
+        sample_files.iter().map(|f| f.display().to_string()).collect(),
+    )?;
+    wakeword.save_to_file(rpw.to_str().unwrap())?;
+    info!("Wake-word {:?} saved to {}.", name, rpw.display());
+
+    register(&rpw)?;
+
+    Ok(rpw)
+}
+
+fn record_samples(name: &str, samples: usize, samples_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = vec![];
+
+    while files.len() < samples {
+        println!("\nSample {}/{}: say \"{}\" once the recording starts.", files.len() + 1, samples, name);
+        std::thread::sleep(Duration::from_secs(1));
+        println!("Recording ...");
+
+        let sample = record(config::ENROLL_SAMPLE_DURATION);
+        let speech = match trim_silence(&sample) {
+            Some(speech) => speech,
+            None => {
+                println!("Nothing was heard, let's try again.");
+                continue
+            }
+        };
+
+        let file = samples_dir.join(format!("sample-{}.wav", files.len() + 1));
+        save_wav(&file, speech)?;
+        println!("Saved {}.", file.display());
+
+        files.push(file);
+    }
+
+    Ok(files)
+}
+
+fn record(duration: Duration) -> Vec<i16> {
+    let frame_length: usize = 512;
+    let mut frame_buffer: Vec<i16> = vec![0; frame_length];
+    let mut out: Vec<i16> = vec![];
+
+    let total = (duration.as_millis() as usize) * (config::RECORDER_SAMPLE_RATE as usize) / 1000;
+    while out.len() < total {
+        recorder::read_microphone(&mut frame_buffer);
+        out.extend_from_slice(&frame_buffer);
+    }
+
+    out
+}
+
+// cut leading & trailing silence (keeping a frame around the speech), None if nothing was said
+fn trim_silence(sample: &[i16]) -> Option<&[i16]> {
+    let frame_length: usize = 512;
+    let loud = sample
+        .chunks(frame_length)
+        .map(|frame| rms(frame) > config::ENROLL_SILENCE_RMS)
+        .collect::<Vec<_>>();
+
+    let first = loud.iter().position(|x| *x)?;
+    let last = loud.iter().rposition(|x| *x)?;
+
+    let start = first.saturating_sub(1) * frame_length;
+    let end = ((last + 2) * frame_length).min(sample.len());
+
+    Some(&sample[start..end])
+}
+
+fn rms(frame: &[i16]) -> f32 {
+    if frame.is_empty() {
+        return 0.
+    }
+
+    let sum = frame.iter().map(|x| (*x as f32) * (*x as f32)).sum::<f32>();
+    (sum / frame.len() as f32).sqrt()
+}
+
+fn save_wav(file: &Path, sample: &[i16]) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: config::RECORDER_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = hound::WavWriter::create(file, spec).map_err(|msg| format!("Cannot write {} ({})", file.display(), msg))?;
+    for x in sample {
+        writer.write_sample(*x).map_err(|msg| format!("Cannot write {} ({})", file.display(), msg))?;
+    }
+    writer.finalize().map_err(|msg| format!("Cannot write {} ({})", file.display(), msg))
+}
+
+// add the wake-word file to settings (enabled), and apply it if the listener is running
+fn register(rpw: &Path) -> Result<(), String> {
+    let path = rpw.display().to_string();
+
+    // settings in memory can't be changed, so update the stored ones
+    let mut settings = db::init_settings();
+    match settings.rustpotter.wake_words.iter_mut().find(|w| w.path == path) {
+        Some(wake_word) => wake_word.enabled = true,
+        None => settings.rustpotter.wake_words.push(WakeWordFile { path, enabled: true })
+    }
+
+    db::save_settings(&settings).map_err(|msg| format!("Cannot save settings ({})", msg))?;
+    info!("Wake-word {} registered in settings.", rpw.display());
+
+    super::reload_rustpotter(&settings.rustpotter).map_err(|_| String::from("Cannot reload wake-word engine"))
+}
+
+// safe file name for the wake phrase
+fn file_name(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
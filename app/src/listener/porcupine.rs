@@ -41,10 +41,11 @@ pub fn init() -> Result<(), ()> {
     Ok(())
 }
 
-pub fn data_callback(frame_buffer: &[i16]) -> Option<i32> {
+pub fn data_callback(frame_buffer: &[i16]) -> Option<String> {
     if let Ok(keyword_index) = PORCUPINE.get().unwrap().process(&frame_buffer) {
         if keyword_index >= 0 {
-            return Some(keyword_index)
+            // keywords are named after their files, there is only the default one for now
+            return Path::new(config::DEFAULT_KEYWORD).file_stem().map(|x| x.to_string_lossy().to_string())
         }
     }
 
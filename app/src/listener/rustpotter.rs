@@ -1,40 +1,55 @@
-use std::path::Path;
 use std::sync::Mutex;
 
-use once_cell::sync::OnceCell;
-use rustpotter::{Rustpotter, RustpotterConfig, WavFmt, DetectorConfig, FiltersConfig, ScoreMode, GainNormalizationConfig, BandPassConfig};
+use once_cell::sync::Lazy;
+use rustpotter::Rustpotter;
 
 use crate::DB;
 use crate::config;
+use crate::db::structs::RustpotterSettings;
 
 // store rustpotter instance
-static RUSTPOTTER: OnceCell<Mutex<Rustpotter>> = OnceCell::new();
+static DETECTOR: Lazy<Mutex<Option<Rustpotter>>> = Lazy::new(|| Mutex::new(None));
 
 pub fn init() -> Result<(), ()> {
-    let rustpotter_config = config::RUSTPOTTER_DEFAULT_CONFIG;
+    reload(&DB.get().unwrap().rustpotter)
+}
+
+// (re)create rustpotter instance with given settings, the current one is kept on failure
+pub fn reload(settings: &RustpotterSettings) -> Result<(), ()> {
+    // detections below the threshold are dropped by rustpotter itself
+    let rustpotter_config = config::rustpotter_config(settings.threshold);
 
     // create rustpotter instance
     match Rustpotter::new(&rustpotter_config) {
         Ok(mut rinstance) => {
-            // success
-            // wake word files list
-            // @TODO. Make it configurable via GUI for custom user voice.
-            let rustpotter_wake_word_files: [&str; 5] = [
-                "rustpotter/jarvis-default.rpw",
-                "rustpotter/jarvis-community-1.rpw",
-                "rustpotter/jarvis-community-2.rpw",
-                "rustpotter/jarvis-community-3.rpw",
-                "rustpotter/jarvis-community-4.rpw",
-                // "rustpotter/jarvis-community-5.rpw",
-            ];
+            // load wake word files, skipping broken ones
+            let mut loaded = 0;
+            for wake_word in settings.wake_words.iter() {
+                if !wake_word.enabled {
+                    info!("Wake-word file {} is disabled, skipping.", wake_word.path);
+                    continue
+                }
 
-            // load wake word files
-            for rpw in rustpotter_wake_word_files {
-                rinstance.add_wakeword_from_file(rpw).unwrap();
+                match rinstance.add_wakeword_from_file(&wake_word.path) {
+                    Ok(_) => {
+                        info!("Wake-word file {} loaded.", wake_word.path);
+                        loaded += 1;
+                    },
+                    Err(msg) => {
+                        error!("Cannot load wake-word file {}, skipping.\nError details: {}", wake_word.path, msg);
+                    }
+                }
             }
 
+            if loaded == 0 {
+                error!("No wake-word files were loaded.");
+                return Err(());
+            }
+
+            info!("Rustpotter initialized with {} wake-word file(s), threshold is {}.", loaded, settings.threshold);
+
             // store
-            RUSTPOTTER.set(Mutex::new(rinstance));
+            *DETECTOR.lock().unwrap() = Some(rinstance);
         },
         Err(msg) => {
             error!("Rustpotter failed to initialize.\nError details: {}", msg);
@@ -46,20 +61,12 @@ pub fn init() -> Result<(), ()> {
     Ok(())
 }
 
-pub fn data_callback(frame_buffer: &[i16]) -> Option<i32> {
-    let mut lock = RUSTPOTTER.get().unwrap().lock();
-    let rustpotter = lock.as_mut().unwrap();
-    let detection = rustpotter.process_i16(&frame_buffer);
+pub fn data_callback(frame_buffer: &[i16]) -> Option<String> {
+    let mut lock = DETECTOR.lock().unwrap();
+    let rustpotter = lock.as_mut()?;
+    let detection = rustpotter.process_i16(frame_buffer)?;
 
-    if let Some(detection) = detection {
-        if detection.score > config::RUSPOTTER_MIN_SCORE {
-            info!("Rustpotter detection info:\n{:?}", detection);
+    info!("Rustpotter detection info:\n{:?}", detection);
 
-            return Some(0)
-        } else {
-            info!("Rustpotter detection info:\n{:?}", detection)
-        }
-    }
-
-    None
-}
\ No newline at end of file
+    Some(detection.name)
+}
@@ -5,7 +5,7 @@ pub fn init() -> Result<(), ()> {
 }
 
 // @TODO. Make it better somehow (more accurate or with higher sensitivity).
-pub fn data_callback(frame_buffer: &[i16]) -> Option<i32> {
+pub fn data_callback(frame_buffer: &[i16]) -> Option<String> {
     // recognize & convert to sequence
     let recognized_phrase = stt::recognize(&frame_buffer, true).unwrap_or("".into());
 
@@ -24,7 +24,7 @@ pub fn data_callback(frame_buffer: &[i16]) -> Option<i32> {
 
             if compare_ratio >= config::VOSK_MIN_RATIO {
                 info!("Phrase activated.");
-                return Some(0)
+                return Some(config::VOSK_FETCH_PHRASE.into())
             }
         }
     }
@@ -1,8 +1,9 @@
 use std::env;
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use once_cell::sync::{Lazy, OnceCell};
+use arc_swap::ArcSwap;
 use platform_dirs::{AppDirs};
 
 // expose the config
@@ -30,8 +31,14 @@ mod recorder;
 // include speech-to-text
 mod stt;
 
+// include voice activity detection
+mod vad;
+
+// include text normalization
+mod normalizer;
+
 // include text-to-speech
-// empty
+mod tts;
 
 // include commands
 mod commands;
@@ -44,6 +51,9 @@ mod audio;
 // include listener
 mod listener;
 
+// include file watching
+mod watcher;
+
 // some global data
 static APP_DIR: Lazy<PathBuf> = Lazy::new(|| {env::current_dir().unwrap()});
 static SOUND_DIR: Lazy<PathBuf> = Lazy::new(|| {APP_DIR.clone().join("sound")});
@@ -51,7 +61,7 @@ static APP_DIRS: OnceCell<AppDirs> = OnceCell::new();
 static APP_CONFIG_DIR: OnceCell<PathBuf> = OnceCell::new();
 static APP_LOG_DIR: OnceCell<PathBuf> = OnceCell::new();
 static DB: OnceCell<db::structs::Settings> = OnceCell::new();
-static COMMANDS_LIST: OnceCell<Vec<AssistantCommand>> = OnceCell::new();
+static COMMANDS_LIST: OnceCell<ArcSwap<Vec<AssistantCommand>>> = OnceCell::new();
 
 fn main() -> Result<(), String> {
     // initialize directories
@@ -68,6 +78,88 @@ fn main() -> Result<(), String> {
     // initialize database (settings)
     DB.set(db::init_settings());
 
+    // init text normalizer (commands are normalized while parsing)
+    if normalizer::init().is_err() {
+        app::close(1); // cannot continue without text normalizer
+    }
+
+    // check command packs and quit, if requested
+    // usage: jarvis-app --check-commands [commands directory]
+    let args: Vec<String> = env::args().collect();
+    if let Some(pos) = args.iter().position(|a| a == "--check-commands") {
+        let dir = args.get(pos + 1).map(|x| x.as_str()).unwrap_or(config::COMMANDS_PATH);
+        app::close(commands::lint::run(Path::new(dir)));
+    }
+
+    // measure wake-word files over labelled samples and quit
+    // usage: jarvis-app --calibrate-wake-word <samples directory>
+    if let Some(pos) = args.iter().position(|a| a == "--calibrate-wake-word") {
+        match args.get(pos + 1) {
+            Some(dir) => app::close(listener::calibration::run(Path::new(dir))),
+            None => {
+                error!("No samples directory given, usage: --calibrate-wake-word <dir>");
+                app::close(1);
+            }
+        }
+    }
+
+    // record a custom wake-word and quit
+    // usage: jarvis-app --enroll-wake-word "джарвис" [--samples N]
+    if let Some(pos) = args.iter().position(|a| a == "--enroll-wake-word") {
+        let name = args.get(pos + 1).cloned().unwrap_or_default();
+        let samples = args.iter()
+            .position(|a| a == "--samples")
+            .and_then(|i| args.get(i + 1))
+            .and_then(|x| x.parse::<usize>().ok())
+            .unwrap_or(config::ENROLL_DEFAULT_SAMPLES);
+
+        if recorder::init().is_err() {
+            app::close(1); // cannot record without recorder
+        }
+
+        match listener::enrollment::run(&name, samples) {
+            Ok(rpw) => {
+                println!("\nWake-word saved to {} and enabled.", rpw.display());
+                app::close(0);
+            },
+            Err(msg) => {
+                error!("Wake-word enrollment failed: {}", msg);
+                println!("\nWake-word enrollment failed: {}", msg);
+                app::close(1);
+            }
+        }
+    }
+
+    // print matched commands instead of executing them
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+
+    // run a text phrase through the commands pipeline and quit, no microphone required
+    // usage: jarvis-app --say "открой браузер" [--dry-run] [--yes]
+    if let Some(pos) = args.iter().position(|a| a == "--say") {
+        let phrase = args.get(pos + 1).cloned().unwrap_or_default();
+        if phrase.is_empty() || phrase.starts_with("--") {
+            error!("No phrase given, usage: --say \"phrase\" [--dry-run] [--yes]");
+            app::close(1);
+        }
+
+        let commands = commands::parse_commands().unwrap();
+        COMMANDS_LIST.set(ArcSwap::from_pointee(commands)).unwrap();
+
+        // commands play sounds
+        if !dry_run && audio::init().is_err() {
+            app::close(1);
+        }
+
+        if !dry_run && tts::init().is_err() {
+            warn!("Continuing without TTS, commands won't speak their results.");
+        }
+
+        // nobody is there to confirm dangerous commands, unless it's done up front
+        let confirmed = args.iter().any(|a| a == "--yes");
+
+        app::close(app::say(&phrase, dry_run, confirmed));
+    }
+
     // initialize tray
     // @TODO. macOS currently not supported for tray functionality,
     // due to the separate thread in which tray processing works,
@@ -87,14 +179,29 @@ fn main() -> Result<(), String> {
         app::close(1); // cannot continue without stt
     }
 
+    // init vad
+    if vad::init().is_err() {
+        app::close(1);
+    }
+
     // init tts engine
-    // none for now (Silero-rs coming)
+    if tts::init().is_err() {
+        warn!("Continuing without TTS, commands won't speak their results.");
+    }
 
     // init commands
     info!("Initializing commands.");
     let commands = commands::parse_commands().unwrap();
     info!("Commands initialized.\nOverall commands parsed: {}\nParsed commands: {:?}", commands.len(), commands::list(&commands));
-    COMMANDS_LIST.set(commands).unwrap();
+
+    // restrict stt to the commands vocabulary (grammar mode only)
+    commands::update_grammar(&commands);
+    COMMANDS_LIST.set(ArcSwap::from_pointee(commands)).unwrap();
+
+    // watch commands directory for changes
+    if commands::watcher::init().is_err() {
+        warn!("Cannot watch commands directory, commands won't be reloaded on change.");
+    }
 
     // init audio
     if audio::init().is_err() {
@@ -107,8 +214,13 @@ fn main() -> Result<(), String> {
         app::close(1); // cannot continue without wake-word engine
     }
 
+    // apply wake-word settings changes (e.g. a newly enrolled wake-word) without restarting
+    if db::watcher::init().is_err() {
+        warn!("Cannot watch settings file, wake-word settings won't be reloaded on change.");
+    }
+
     // start the app
-    app::start();
+    app::start(dry_run);
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+mod russian;
+
+use once_cell::sync::OnceCell;
+use crate::config;
+
+use crate::config::structs::TextNormalizer;
+
+static NORMALIZER_TYPE: OnceCell<TextNormalizer> = OnceCell::new();
+
+pub fn init() -> Result<(), ()> {
+    if NORMALIZER_TYPE.get().is_some() {return Ok(());} // already initialized
+
+    // set default normalizer
+    // @TODO. Make it configurable, when more languages are supported.
+    NORMALIZER_TYPE.set(config::DEFAULT_TEXT_NORMALIZER).unwrap();
+
+    info!("Using {:?} text normalizer.", NORMALIZER_TYPE.get().unwrap());
+
+    Ok(())
+}
+
+// convert recognized text into the form commands are matched against (number words to digits, etc.)
+pub fn normalize(text: &str) -> String {
+    match NORMALIZER_TYPE.get().unwrap() {
+        TextNormalizer::Russian => {
+            russian::normalize(text)
+        }
+    }
+}
+
+
+// words the normalizer is able to convert
+pub fn vocabulary() -> Vec<String> {
+    match NORMALIZER_TYPE.get().unwrap() {
+        TextNormalizer::Russian => {
+            russian::vocabulary()
+        }
+    }
+}
\ No newline at end of file
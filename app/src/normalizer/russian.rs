@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+
+// cardinal numerals with their case forms (ё is replaced with е before lookup)
+const CARDINALS: &[(u64, &[&str])] = &[
+    (0, &["ноль", "нуль", "ноля", "нуля", "нолю", "нулю", "нолем", "нулем", "ноле", "нуле"]),
+    (1, &["один", "одна", "одно", "одного", "одной", "одному", "одним", "одном", "одну", "одною"]),
+    (2, &["два", "две", "двух", "двум", "двумя"]),
+    (3, &["три", "трех", "трем", "тремя"]),
+    (4, &["четыре", "четырех", "четырем", "четырьмя"]),
+    (8, &["восемь", "восьми", "восемью", "восьмью"]),
+    (40, &["сорок", "сорока"]),
+    (50, &["пятьдесят", "пятидесяти", "пятьюдесятью"]),
+    (60, &["шестьдесят", "шестидесяти", "шестьюдесятью"]),
+    (70, &["семьдесят", "семидесяти", "семьюдесятью"]),
+    (80, &["восемьдесят", "восьмидесяти", "восемьюдесятью"]),
+    (90, &["девяносто", "девяноста"]),
+    (100, &["сто", "ста"]),
+    (200, &["двести", "двухсот", "двумстам", "двумястами", "двухстах"]),
+    (300, &["триста", "трехсот", "тремстам", "тремястами", "трехстах"]),
+    (400, &["четыреста", "четырехсот", "четыремстам", "четырьмястами", "четырехстах"]),
+    (500, &["пятьсот", "пятисот", "пятистам", "пятьюстами", "пятистах"]),
+    (600, &["шестьсот", "шестисот", "шестистам", "шестьюстами", "шестистах"]),
+    (700, &["семьсот", "семисот", "семистам", "семьюстами", "семистах"]),
+    (800, &["восемьсот", "восьмисот", "восьмистам", "восемьюстами", "восьмистах"]),
+    (900, &["девятьсот", "девятисот", "девятистам", "девятьюстами", "девятистах"]),
+    (1000, &["тысяча", "тысячи", "тысяч", "тысяче", "тысячу", "тысячей", "тысячам", "тысячами", "тысячах"]),
+    (1000000, &["миллион", "миллиона", "миллиону", "миллионом", "миллионе", "миллионы", "миллионов", "миллионам", "миллионами", "миллионах"]),
+];
+
+// cardinals declined like "пять" (пять, пяти, пятью)
+const SOFT_CARDINAL_STEMS: &[(u64, &str)] = &[
+    (5, "пят"), (6, "шест"), (7, "сем"), (9, "девят"), (10, "десят"),
+    (11, "одиннадцат"), (12, "двенадцат"), (13, "тринадцат"), (14, "четырнадцат"),
+    (15, "пятнадцат"), (16, "шестнадцат"), (17, "семнадцат"), (18, "восемнадцат"),
+    (19, "девятнадцат"), (20, "двадцат"), (30, "тридцат"),
+];
+const SOFT_CARDINAL_ENDINGS: &[&str] = &["ь", "и", "ью"];
+
+// ordinals are matched as stem + adjective ending
+const ORDINAL_STEMS: &[(u64, &str)] = &[
+    (1, "перв"), (2, "втор"), (3, "трет"), (4, "четверт"), (5, "пят"),
+    (6, "шест"), (7, "седьм"), (8, "восьм"), (9, "девят"), (10, "десят"),
+    (11, "одиннадцат"), (12, "двенадцат"), (13, "тринадцат"), (14, "четырнадцат"),
+    (15, "пятнадцат"), (16, "шестнадцат"), (17, "семнадцат"), (18, "восемнадцат"),
+    (19, "девятнадцат"), (20, "двадцат"), (30, "тридцат"), (40, "сороков"),
+    (50, "пятидесят"), (60, "шестидесят"), (70, "семидесят"), (80, "восьмидесят"),
+    (90, "девяност"), (100, "сот"), (200, "двухсот"), (300, "трехсот"),
+    (400, "четырехсот"), (500, "пятисот"), (600, "шестисот"), (700, "семисот"),
+    (800, "восьмисот"), (900, "девятисот"), (1000, "тысячн"),
+];
+const ORDINAL_ENDINGS: &[&str] = &[
+    "ый", "ой", "ий", "ая", "яя", "ое", "ее", "ого", "его", "ому", "ему",
+    "ым", "им", "ом", "ем", "ую", "юю", "ые", "ие", "ых", "их", "ыми", "ими",
+    "ья", "ье", "ьего", "ьей", "ьему", "ьим", "ьем", "ью", "ьи", "ьих", "ьими",
+];
+
+static CARDINAL_FORMS: Lazy<HashMap<String, u64>> = Lazy::new(|| {
+    let mut forms = HashMap::new();
+
+    for (value, words) in CARDINALS {
+        for word in words.iter() {
+            forms.insert(String::from(*word), *value);
+        }
+    }
+
+    for (value, stem) in SOFT_CARDINAL_STEMS {
+        for ending in SOFT_CARDINAL_ENDINGS {
+            forms.insert(format!("{}{}", stem, ending), *value);
+        }
+    }
+
+    forms
+});
+
+#[derive(Debug, Clone, Copy)]
+struct NumberWord {
+    value: u64,
+    ordinal: bool,
+}
+
+fn parse_word(word: &str) -> Option<NumberWord> {
+    if let Some(value) = CARDINAL_FORMS.get(word) {
+        return Some(NumberWord { value: *value, ordinal: false })
+    }
+
+    // longest matching stem wins
+    let mut result: Option<(usize, u64)> = None;
+    for (value, stem) in ORDINAL_STEMS {
+        if let Some(ending) = word.strip_prefix(stem) {
+            if ORDINAL_ENDINGS.contains(&ending) && result.is_none_or(|(len, _)| stem.len() > len) {
+                result = Some((stem.len(), *value));
+            }
+        }
+    }
+
+    result.map(|(_, value)| NumberWord { value, ordinal: true })
+}
+
+// 1 - units & teens, 2 - tens, 3 - hundreds
+fn order(value: u64) -> u8 {
+    match value {
+        0..=19 => 1,
+        20..=99 => 2,
+        _ => 3,
+    }
+}
+
+// accumulates consecutive number words into a single number
+struct NumberBuilder {
+    total: u64,
+    group: u64,
+    last_order: u8,
+    last_scale: u64,
+    started: bool,
+}
+
+impl NumberBuilder {
+    fn new() -> Self {
+        NumberBuilder { total: 0, group: 0, last_order: 4, last_scale: u64::MAX, started: false }
+    }
+
+    fn accepts(&self, word: NumberWord) -> bool {
+        if !self.started {
+            return true
+        }
+
+        match word.value {
+            0 => false,
+            v if v >= 1000 => v < self.last_scale,
+            // "двадцать двенадцать" is two numbers
+            v @ 10..=19 => order(v) < self.last_order && self.last_order != 2,
+            v => order(v) < self.last_order,
+        }
+    }
+
+    fn push(&mut self, word: NumberWord) {
+        self.started = true;
+
+        match word.value {
+            v if v >= 1000 => {
+                self.total += self.group.max(1) * v;
+                self.group = 0;
+                self.last_order = 4;
+                self.last_scale = v;
+            },
+            v => {
+                self.group += v;
+                self.last_order = order(v);
+            }
+        }
+    }
+
+    fn flush(&mut self, out: &mut Vec<String>) {
+        if self.started {
+            out.push((self.total + self.group).to_string());
+        }
+
+        *self = NumberBuilder::new();
+    }
+}
+
+pub fn normalize(text: &str) -> String {
+    let mut out: Vec<String> = vec![];
+    let mut number = NumberBuilder::new();
+
+    for word in text.split_whitespace() {
+        let key = word.to_lowercase().replace('ё', "е");
+
+        match parse_word(&key) {
+            Some(number_word) => {
+                if !number.accepts(number_word) {
+                    number.flush(&mut out);
+                }
+
+                number.push(number_word);
+
+                // ordinals and zero always end the number
+                if number_word.ordinal || number_word.value == 0 {
+                    number.flush(&mut out);
+                }
+            },
+            None => {
+                number.flush(&mut out);
+                out.push(word.into());
+            }
+        }
+    }
+
+    number.flush(&mut out);
+    out.join(" ")
+}
+
+pub fn vocabulary() -> Vec<String> {
+    CARDINAL_FORMS.keys().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+
+    #[test]
+    fn converts_number_words() {
+        let table = [
+            ("поставь таймер на пять минут", "поставь таймер на 5 минут"),
+            ("двадцать пять", "25"),
+            ("сто сорок", "140"),
+            ("громкость на сорок процентов", "громкость на 40 процентов"),
+            ("таймер на двадцать одну минуту", "таймер на 21 минуту"),
+            ("через пятнадцать минут", "через 15 минут"),
+            ("двести двадцать два", "222"),
+            ("девятьсот девяносто девять", "999"),
+            ("две тысячи двадцать три", "2023"),
+            ("тысяча", "1000"),
+            ("сто тысяч триста", "100300"),
+            ("три миллиона", "3000000"),
+            ("ноль", "0"),
+        ];
+
+        for (input, expected) in table {
+            assert_eq!(normalize(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn converts_case_forms() {
+        let table = [
+            ("с пятью друзьями", "с 5 друзьями"),
+            ("больше двадцати", "больше 20"),
+            ("из четырёх", "из 4"),
+            ("двумястами", "200"),
+            ("около трехсот пятидесяти", "около 350"),
+            ("восьмью", "8"),
+        ];
+
+        for (input, expected) in table {
+            assert_eq!(normalize(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn converts_ordinals() {
+        let table = [
+            ("первый", "1"),
+            ("второй вариант", "2 вариант"),
+            ("третья", "3"),
+            ("на четвёртом этаже", "на 4 этаже"),
+            ("двадцать пятое", "25"),
+            ("сороковой", "40"),
+            ("сотый", "100"),
+        ];
+
+        for (input, expected) in table {
+            assert_eq!(normalize(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn splits_unrelated_numbers() {
+        let table = [
+            ("пять двадцать", "5 20"),
+            ("двадцать двенадцать", "20 12"),
+            ("первый второй", "1 2"),
+            ("ноль пять", "0 5"),
+        ];
+
+        for (input, expected) in table {
+            assert_eq!(normalize(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn keeps_other_words() {
+        assert_eq!(normalize("открой браузер"), "открой браузер");
+        assert_eq!(normalize(""), "");
+    }
+}
@@ -1,9 +1,14 @@
 mod vosk;
 
+mod structs;
+pub use structs::*;
+
 use once_cell::sync::OnceCell;
 use crate::config;
 
 use crate::config::structs::SpeechToTextEngine;
+use crate::config::structs::SpeechToTextMode;
+use crate::DB;
 
 static STT_TYPE: OnceCell<SpeechToTextEngine> = OnceCell::new();
 
@@ -27,10 +32,36 @@ pub fn init() -> Result<(), ()> {
     Ok(())
 }
 
+pub fn grammar_enabled() -> bool {
+    DB.get().unwrap().speech_to_text_mode == SpeechToTextMode::Grammar
+}
+
+// restrict recognition to the given vocabulary
+pub fn set_grammar(words: &[String]) -> Result<(), ()> {
+    let mut grammar = words.to_vec();
+    grammar.push(config::VOSK_UNKNOWN_WORD.into());
+
+    match STT_TYPE.get().unwrap() {
+        SpeechToTextEngine::Vosk => {
+            info!("Building Vosk grammar of {} words.", grammar.len());
+            vosk::set_grammar(Some(grammar.as_slice()))
+        }
+    }
+}
+
 pub fn recognize(data: &[i16], partial: bool) -> Option<String> {
     match STT_TYPE.get().unwrap() {
         SpeechToTextEngine::Vosk => {
             vosk::recognize(data, partial)
         }
     }
+}
+
+// n-best list of final results (partials are never returned here)
+pub fn recognize_alternatives(data: &[i16]) -> Option<Vec<Alternative>> {
+    match STT_TYPE.get().unwrap() {
+        SpeechToTextEngine::Vosk => {
+            vosk::recognize_alternatives(data)
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,5 @@
+#[derive(Debug, Clone)]
+pub struct Alternative {
+    pub text: String,
+    pub confidence: f32,
+}
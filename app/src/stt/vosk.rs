@@ -4,6 +4,7 @@ use vosk::{DecodingState, Model, Recognizer};
 use std::sync::Mutex;
 
 use crate::config::VOSK_MODEL_PATH;
+use super::Alternative;
 
 static MODEL: OnceCell<Model> = OnceCell::new();
 static RECOGNIZER: OnceCell<Mutex<Recognizer>> = OnceCell::new();
@@ -12,14 +13,39 @@ pub fn init_vosk() {
     if !RECOGNIZER.get().is_none() {return;} // already initialized
 
     let model = Model::new(VOSK_MODEL_PATH).unwrap();
-    let mut recognizer = Recognizer::new(&model, 16000.0).unwrap();
+    let recognizer = create_recognizer(&model, None).unwrap();
+
+    MODEL.set(model);
+    RECOGNIZER.set(Mutex::new(recognizer));
+}
+
+fn create_recognizer(model: &Model, grammar: Option<&[String]>) -> Option<Recognizer> {
+    let mut recognizer = match grammar {
+        Some(grammar) => Recognizer::new_with_grammar(model, 16000.0, grammar)?,
+        None => Recognizer::new(model, 16000.0)?
+    };
 
     recognizer.set_max_alternatives(10);
     recognizer.set_words(true);
     recognizer.set_partial_words(true);
 
-    MODEL.set(model);
-    RECOGNIZER.set(Mutex::new(recognizer));
+    Some(recognizer)
+}
+
+// replace the active recognizer with the one restricted to the given grammar (None for free-form)
+pub fn set_grammar(grammar: Option<&[String]>) -> Result<(), ()> {
+    match create_recognizer(MODEL.get().unwrap(), grammar) {
+        Some(recognizer) => {
+            *RECOGNIZER.get().unwrap().lock().unwrap() = recognizer;
+
+            Ok(())
+        },
+        None => {
+            error!("Failed to create Vosk recognizer (grammar: {:?}).", grammar);
+
+            Err(())
+        }
+    }
 }
 
 pub fn recognize(data: &[i16], include_partial: bool) -> Option<String> {
@@ -51,6 +77,30 @@ pub fn recognize(data: &[i16], include_partial: bool) -> Option<String> {
     }
 }
 
+pub fn recognize_alternatives(data: &[i16]) -> Option<Vec<Alternative>> {
+    let mut recognizer = RECOGNIZER.get().unwrap().lock().unwrap();
+
+    match recognizer.accept_waveform(data) {
+        DecodingState::Finalized => {
+            // Result will always be multiple because we called set_max_alternatives
+            Some(
+                recognizer
+                    .result()
+                    .multiple()
+                    .unwrap()
+                    .alternatives
+                    .iter()
+                    .map(|alt| Alternative {
+                        text: alt.text.into(),
+                        confidence: alt.confidence,
+                    })
+                    .collect()
+            )
+        }
+        _ => None,
+    }
+}
+
 // pub fn stereo_to_mono(input_data: &[i16]) -> Vec<i16> {
 //     let mut result = Vec::with_capacity(input_data.len() / 2);
 //     result.extend(
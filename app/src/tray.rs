@@ -71,7 +71,7 @@ pub fn init() {
                 println!("menu event: {:?}", event);
 
                 if event.id == 1002 {
-                    std::process::exit(0);
+                    crate::app::close(0);
                 }
             }
         });
@@ -0,0 +1,111 @@
+mod espeak;
+mod rhvoice;
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use once_cell::sync::OnceCell;
+
+use crate::{config, audio, DB};
+use crate::config::structs::TextToSpeechEngine;
+
+static TTS_TYPE: OnceCell<TextToSpeechEngine> = OnceCell::new();
+
+pub fn init() -> Result<(), ()> {
+    if TTS_TYPE.get().is_some() {return Ok(());} // already initialized
+
+    // set tts engine from settings
+    let engine = DB.get().unwrap().text_to_speech_engine;
+
+    // load given tts engine
+    let result = match engine {
+        TextToSpeechEngine::EspeakNg => {
+            info!("Initializing eSpeak NG TTS backend.");
+            espeak::init()
+        },
+        TextToSpeechEngine::RhVoice => {
+            info!("Initializing RHVoice TTS backend.");
+            rhvoice::init()
+        }
+    };
+
+    match result {
+        Ok(_) => {
+            TTS_TYPE.set(engine).unwrap();
+            info!("TTS backend initialized.");
+
+            Ok(())
+        },
+        Err(msg) => {
+            error!("Failed to initialize TTS backend.\nError details: {}", msg);
+
+            Err(())
+        }
+    }
+}
+
+// synthesize text into the given wav file
+pub fn synthesize(text: &str, out: &Path) -> Result<(), String> {
+    match TTS_TYPE.get() {
+        Some(TextToSpeechEngine::EspeakNg) => espeak::synthesize(text, out),
+        Some(TextToSpeechEngine::RhVoice) => rhvoice::synthesize(text, out),
+        None => Err("TTS is not initialized".into())
+    }
+}
+
+// say the text out loud
+pub fn speak(text: &str) -> Result<(), String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(())
+    }
+
+    let out = output_path();
+    info!("Speaking: {}", text);
+
+    synthesize(text, &out)?;
+    audio::play_sound(&out);
+
+    Ok(())
+}
+
+fn output_path() -> PathBuf {
+    std::env::temp_dir().join(config::TTS_OUTPUT_FILE_NAME)
+}
+
+// voice from settings, or the engine default
+fn voice(default: &str) -> String {
+    match DB.get().unwrap().text_to_speech_voice.as_str() {
+        "" => default.into(),
+        voice => voice.into()
+    }
+}
+
+// make sure the engine binary can be run
+fn check_binary(bin: &str, arg: &str) -> Result<(), String> {
+    match Command::new(bin).arg(arg).output() {
+        Ok(_) => Ok(()),
+        Err(msg) => Err(format!("Cannot run {} (is it installed?): {}", bin, msg))
+    }
+}
+
+// run the engine, feeding the text through stdin (so it's never taken for an option)
+fn run_with_text(mut command: Command, text: &str) -> Result<(), String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|msg| format!("Cannot run TTS engine ({})", msg))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).map_err(|msg| format!("Cannot pass text to TTS engine ({})", msg))?;
+    }
+
+    let output = child.wait_with_output().map_err(|msg| format!("TTS engine error ({})", msg))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("TTS engine failed with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
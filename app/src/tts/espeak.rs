@@ -0,0 +1,18 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::config;
+
+pub fn init() -> Result<(), String> {
+    super::check_binary(config::ESPEAK_NG_BIN, "--version")
+}
+
+pub fn synthesize(text: &str, out: &Path) -> Result<(), String> {
+    let mut command = Command::new(config::ESPEAK_NG_BIN);
+    command
+        .arg("-v").arg(super::voice(config::ESPEAK_NG_DEFAULT_VOICE))
+        .arg("-w").arg(out)
+        .arg("--stdin");
+
+    super::run_with_text(command, text)
+}
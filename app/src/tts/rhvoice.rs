@@ -0,0 +1,17 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::config;
+
+pub fn init() -> Result<(), String> {
+    super::check_binary(config::RHVOICE_BIN, "--help")
+}
+
+pub fn synthesize(text: &str, out: &Path) -> Result<(), String> {
+    let mut command = Command::new(config::RHVOICE_BIN);
+    command
+        .arg("-p").arg(super::voice(config::RHVOICE_DEFAULT_VOICE))
+        .arg("-o").arg(out);
+
+    super::run_with_text(command, text)
+}
@@ -0,0 +1,82 @@
+mod energy;
+
+use std::collections::VecDeque;
+use once_cell::sync::OnceCell;
+
+use crate::{config, DB};
+use crate::config::structs::VoiceActivityDetector;
+
+static VAD_TYPE: OnceCell<VoiceActivityDetector> = OnceCell::new();
+
+pub fn init() -> Result<(), ()> {
+    if VAD_TYPE.get().is_some() {return Ok(());} // already initialized
+
+    // set vad type from settings
+    VAD_TYPE.set(DB.get().unwrap().voice_activity_detector).unwrap();
+
+    // load given vad
+    match VAD_TYPE.get().unwrap() {
+        VoiceActivityDetector::Energy => {
+            info!("Initializing energy based VAD.");
+            energy::init();
+        },
+        VoiceActivityDetector::Disabled => {
+            info!("VAD is disabled, every frame is processed.");
+        }
+    }
+
+    Ok(())
+}
+
+// whether the frame (or the one shortly before it) contains speech
+pub fn is_speech(frame_buffer: &[i16]) -> bool {
+    match VAD_TYPE.get() {
+        Some(VoiceActivityDetector::Energy) => energy::is_speech(frame_buffer),
+        _ => true
+    }
+}
+
+// the last frames before the speech starts, so that quiet (or hissy) onsets aren't lost
+#[derive(Default)]
+pub struct PreSpeech {
+    frames: VecDeque<Vec<i16>>,
+}
+
+impl PreSpeech {
+    // remember the frame, dropping the oldest one
+    pub fn push(&mut self, frame_buffer: &[i16]) {
+        if self.frames.len() == config::VAD_PRE_SPEECH_FRAMES {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(frame_buffer.to_vec());
+    }
+
+    // remembered frames followed by the given one, the buffer is emptied
+    pub fn take_with(&mut self, frame_buffer: &[i16]) -> Vec<i16> {
+        let mut out = self.frames.drain(..).flatten().collect::<Vec<_>>();
+        out.extend_from_slice(frame_buffer);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_last_frames() {
+        let mut pre_speech = PreSpeech::default();
+        for i in 0..config::VAD_PRE_SPEECH_FRAMES as i16 + 2 {
+            pre_speech.push(&[i, i]);
+        }
+
+        let data = pre_speech.take_with(&[-1, -1]);
+        assert_eq!(data.len(), (config::VAD_PRE_SPEECH_FRAMES + 1) * 2);
+        assert_eq!(&data[..2], &[2, 2]);
+        assert_eq!(&data[data.len() - 2..], &[-1, -1]);
+
+        // emptied
+        assert_eq!(pre_speech.take_with(&[0]), vec![0]);
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+
+use crate::config;
+
+struct State {
+    // average energy of non-speech frames
+    noise_floor: f32,
+    // frames left to report as speech after it stops (covers short pauses & quiet sounds)
+    hangover: usize,
+}
+
+static STATE: Lazy<Mutex<State>> = Lazy::new(|| Mutex::new(State {
+    noise_floor: config::VAD_MIN_ENERGY,
+    hangover: 0,
+}));
+
+pub fn init() {
+    let mut state = STATE.lock().unwrap();
+    state.noise_floor = config::VAD_MIN_ENERGY;
+    state.hangover = 0;
+}
+
+pub fn is_speech(frame_buffer: &[i16]) -> bool {
+    let mut state = STATE.lock().unwrap();
+
+    let energy = rms(frame_buffer);
+    let threshold = (state.noise_floor * config::VAD_ENERGY_RATIO).max(config::VAD_MIN_ENERGY);
+
+    // loud enough and not a hiss (noise crosses zero much more often than voice)
+    if energy > threshold && zero_crossing_rate(frame_buffer) < config::VAD_MAX_ZERO_CROSSING_RATE {
+        state.hangover = config::VAD_HANGOVER_FRAMES;
+        return true
+    }
+
+    // follow the background noise, quicker when it gets quieter
+    let rate = if energy < state.noise_floor { config::VAD_NOISE_ADAPT_DOWN } else { config::VAD_NOISE_ADAPT_UP };
+    state.noise_floor += (energy - state.noise_floor) * rate;
+
+    if state.hangover > 0 {
+        state.hangover -= 1;
+        return true
+    }
+
+    false
+}
+
+fn rms(frame: &[i16]) -> f32 {
+    if frame.is_empty() {
+        return 0.
+    }
+
+    let sum = frame.iter().map(|x| (*x as f32) * (*x as f32)).sum::<f32>();
+    (sum / frame.len() as f32).sqrt()
+}
+
+// share of neighbouring samples with different signs
+fn zero_crossing_rate(frame: &[i16]) -> f32 {
+    if frame.len() < 2 {
+        return 0.
+    }
+
+    let crossings = frame.windows(2).filter(|w| (w[0] >= 0) != (w[1] >= 0)).count();
+    crossings as f32 / (frame.len() - 1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::{init, is_speech};
+    use crate::config;
+
+    // detector state is shared, so tests must not run at the same time
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    fn tone(amplitude: f32, frequency: f32) -> Vec<i16> {
+        (0..512).map(|i| {
+            let t = i as f32 / config::RECORDER_SAMPLE_RATE as f32;
+            (amplitude * (2. * std::f32::consts::PI * frequency * t).sin()) as i16
+        }).collect()
+    }
+
+    #[test]
+    fn detects_speech() {
+        let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+
+        let silence = vec![0; 512];
+        let voice = tone(3000., 300.);
+        let hiss: Vec<i16> = (0..512).map(|i| if i % 2 == 0 { 3000 } else { -3000 }).collect();
+
+        assert!(!is_speech(&silence));
+        assert!(!is_speech(&hiss));
+        assert!(is_speech(&voice));
+    }
+
+    #[test]
+    fn holds_speech_over_short_pauses() {
+        let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+
+        let silence = vec![0; 512];
+        assert!(is_speech(&tone(3000., 300.)));
+
+        for _ in 0..config::VAD_HANGOVER_FRAMES {
+            assert!(is_speech(&silence));
+        }
+        assert!(!is_speech(&silence));
+    }
+
+    #[test]
+    fn follows_background_noise() {
+        let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+
+        // quiet speech is heard in a silent room
+        let quiet_voice = tone(990., 300.);
+        assert!(is_speech(&quiet_voice));
+
+        // but not over a steady hum
+        init();
+        let hum = tone(400., 100.);
+        for _ in 0..1000 {
+            assert!(!is_speech(&hum));
+        }
+        assert!(!is_speech(&quiet_voice));
+        assert!(is_speech(&tone(3000., 300.)));
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, RecursiveMode, Watcher};
+
+// watch `path` in background, `on_change` is called once a burst of relevant events settles
+// `name` is only used in the logs
+pub fn watch<R, C>(name: &str, path: &Path, mode: RecursiveMode, delay: Duration, is_relevant: R, mut on_change: C) -> Result<(), ()>
+where
+    R: Fn(&Event) -> bool + Send + 'static,
+    C: FnMut() + Send + 'static,
+{
+    let (tx, rx) = channel::<notify::Result<Event>>();
+
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(w) => w,
+        Err(msg) => {
+            error!("Failed to create {} watcher.\nError details: {}", name, msg);
+
+            return Err(())
+        }
+    };
+
+    if let Err(msg) = watcher.watch(path, mode) {
+        error!("Failed to watch {}.\nError details: {}", path.display(), msg);
+
+        return Err(())
+    }
+
+    info!("Watching {} for changes.", path.display());
+
+    let name = String::from(name);
+    thread::spawn(move || {
+        // keep watcher alive as long as the thread runs
+        let _watcher = watcher;
+
+        while let Ok(event) = rx.recv() {
+            match event {
+                Ok(event) if is_relevant(&event) => (),
+                Ok(_) => continue,
+                Err(msg) => {
+                    warn!("{} watcher error: {}", name, msg);
+                    continue
+                }
+            }
+
+            // editors usually produce a burst of events, wait for it to settle
+            thread::sleep(delay);
+            while rx.try_recv().is_ok() {}
+
+            on_change();
+        }
+    });
+
+    Ok(())
+}